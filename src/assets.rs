@@ -0,0 +1,229 @@
+//! Loads files from layered asset directories once and hands out typed handles to them.
+//!
+//! Each path is looked up in the directories added last first, then among the files built
+//! into the executable with [`Assets::embed`]. That lets a mods directory override the
+//! installed data, which overrides the built-in defaults, without touching any of them.
+//!
+//! Loaded files are watched by polling their modification time, see [`Assets::reload_changed`].
+
+use crate::error::GameError;
+use std::{
+    any::Any,
+    collections::HashMap,
+    fs, io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Something that can be loaded from the bytes of a file.
+pub trait Asset: Any + Sized {
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self>;
+}
+
+/// Identifies a loaded asset regardless of its type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AssetId(usize);
+
+/// A loaded asset of type `T`, cheap to copy around.
+pub struct Handle<T> {
+    id: AssetId,
+    _asset: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub fn id(&self) -> AssetId {
+        self.id
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+/// An asset that changed on disk since it was loaded.
+pub struct Reload {
+    pub id: AssetId,
+    pub path: PathBuf,
+    /// When reloading fails the previous version is kept.
+    pub result: io::Result<()>,
+}
+
+/// Where the current version of an asset was read from.
+#[derive(Clone, PartialEq)]
+enum Source {
+    File {
+        path: PathBuf,
+        modified: Option<SystemTime>,
+    },
+    Embedded,
+}
+
+struct Entry {
+    path: PathBuf,
+    source: Source,
+    value: Box<dyn Any>,
+    parse: fn(&[u8]) -> io::Result<Box<dyn Any>>,
+}
+
+/// Caches the assets loaded from a stack of directories.
+#[derive(Default)]
+pub struct Assets {
+    dirs: Vec<PathBuf>,
+    entries: Vec<Entry>,
+    ids: HashMap<PathBuf, AssetId>,
+    embedded: HashMap<PathBuf, &'static [u8]>,
+}
+
+impl Assets {
+    /// Only embedded assets can be loaded until directories are added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a directory whose files override the ones in the directories added before it.
+    pub fn add_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.dirs.push(dir.into());
+    }
+
+    /// Provides the contents of `path` for when it's missing from every directory,
+    /// usually with `include_bytes!`.
+    pub fn embed(&mut self, path: impl Into<PathBuf>, bytes: &'static [u8]) {
+        self.embedded.insert(path.into(), bytes);
+    }
+
+    /// Loads the asset at `path`, or returns the handle it was already loaded under.
+    ///
+    /// Loading the same path as two different types is an `InvalidInput` error.
+    pub fn load<T: Asset>(&mut self, path: impl AsRef<Path>) -> Result<Handle<T>, GameError> {
+        let path = path.as_ref().to_path_buf();
+        self.try_load(&path)
+            .map_err(|source| GameError::Asset { path, source })
+    }
+
+    fn try_load<T: Asset>(&mut self, path: &Path) -> io::Result<Handle<T>> {
+        let path = path.to_path_buf();
+        let handle = |id| Handle {
+            id,
+            _asset: PhantomData,
+        };
+        if let Some(&id) = self.ids.get(&path) {
+            return match self.entries[id.0].value.is::<T>() {
+                true => Ok(handle(id)),
+                false => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "already loaded as another type",
+                )),
+            };
+        }
+
+        let source = self
+            .find(&path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not in any asset directory"))?;
+        let parse =
+            |bytes: &[u8]| -> io::Result<Box<dyn Any>> { Ok(Box::new(T::from_bytes(bytes)?)) };
+        let value = self.read(&path, &source, parse)?;
+        let id = AssetId(self.entries.len());
+        self.entries.push(Entry {
+            path: path.clone(),
+            source,
+            value,
+            parse,
+        });
+        self.ids.insert(path, id);
+        Ok(handle(id))
+    }
+
+    /// The highest priority place `path` can be read from.
+    fn find(&self, path: &Path) -> Option<Source> {
+        let file = self
+            .dirs
+            .iter()
+            .rev()
+            .map(|dir| dir.join(path))
+            .find(|p| p.is_file());
+        match file {
+            Some(path) => Some(Source::File {
+                modified: modified(&path),
+                path,
+            }),
+            None if self.embedded.contains_key(path) => Some(Source::Embedded),
+            None => None,
+        }
+    }
+
+    fn read(
+        &self,
+        path: &Path,
+        source: &Source,
+        parse: fn(&[u8]) -> io::Result<Box<dyn Any>>,
+    ) -> io::Result<Box<dyn Any>> {
+        match source {
+            Source::File { path, .. } => fs::read(path)
+                .and_then(|bytes| parse(&bytes))
+                .map_err(|e| with_path(e, path)),
+            Source::Embedded => parse(self.embedded[path]).map_err(|e| with_path(e, path)),
+        }
+    }
+
+    pub fn get<T: Asset>(&self, handle: Handle<T>) -> &T {
+        self.entries[handle.id.0]
+            .value
+            .downcast_ref()
+            .expect("handles always match the type of their asset")
+    }
+
+    /// Path of an asset, relative to the asset directories.
+    pub fn path(&self, id: AssetId) -> &Path {
+        &self.entries[id.0].path
+    }
+
+    /// The file an asset was read from, `None` if it's built into the executable.
+    pub fn source(&self, id: AssetId) -> Option<&Path> {
+        match &self.entries[id.0].source {
+            Source::File { path, .. } => Some(path),
+            Source::Embedded => None,
+        }
+    }
+
+    /// Reloads every asset whose file changed since it was last read. That includes files
+    /// that were added to override an asset, or deleted so the one underneath shows through.
+    pub fn reload_changed(&mut self) -> Vec<Reload> {
+        let mut reloads = Vec::new();
+        for i in 0..self.entries.len() {
+            let entry = &self.entries[i];
+            let source = match self.find(&entry.path) {
+                Some(source) if source != entry.source => source,
+                _ => continue,
+            };
+            let result = self.read(&entry.path, &source, entry.parse);
+            let entry = &mut self.entries[i];
+            // Remember the new version even when it's broken so it's only reported once
+            entry.source = source;
+            reloads.push(Reload {
+                id: AssetId(i),
+                path: entry.path.clone(),
+                result: result.map(|value| entry.value = value),
+            });
+        }
+        reloads
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Errors from `std::fs` don't say which file they're about.
+fn with_path(error: io::Error, path: &Path) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
+
+/// Reads the bytes as UTF-8 text, for assets stored as text files.
+pub fn text(bytes: &[u8]) -> io::Result<&str> {
+    std::str::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,276 @@
+//! Moves circles through a [`Map`] without letting them overlap solid tiles.
+
+use crate::{map::Map, math::Vec2};
+
+/// Anything that moves through the map and collides with it.
+#[derive(Clone, Copy, Debug)]
+pub struct Body {
+    pub radius: f32,
+    /// How much higher than its current floor a tile can be before it blocks the body.
+    pub step_height: f32,
+}
+
+/// Floor height of a tile, solid tiles and the outside of the map are infinitely high.
+/// Tiles only blocked along a segment have floor around it.
+///
+/// Every other tile is flat for now, this is where per-tile heights will plug in.
+pub fn tile_height(map: &Map, x: i64, y: i64) -> f32 {
+    if x < 0 || y < 0 {
+        return f32::INFINITY;
+    }
+    match map.get(x as usize, y as usize) {
+        Some(tile) if !map.is_solid(tile) || map.segment(tile).is_some() => 0.0,
+        _ => f32::INFINITY,
+    }
+}
+
+/// Most sub-steps a single [`slide`] takes, so absurd motions can't stall a tick.
+const MAX_STEPS: f32 = 256.0;
+
+/// Moves `body` from `from` by `motion` and returns where it ends up.
+///
+/// The motion is swept in steps no longer than the body's radius so fast bodies can't
+/// tunnel through walls, and a longer motion is cut short after [`MAX_STEPS`] of them.
+/// When a wall is hit, only the part of the motion going into it is dropped, so the body
+/// slides along walls and around corners. A motion that isn't finite leaves the body
+/// where it is.
+pub fn slide(map: &Map, body: &Body, from: Vec2, motion: Vec2) -> Vec2 {
+    if !motion.is_finite() {
+        return from;
+    }
+    let max_step = body.radius.clamp(0.05, 0.5);
+    let reach = MAX_STEPS * max_step;
+    // Clamping each axis first keeps the length of huge motions from overflowing.
+    let motion = motion
+        .clamp(Vec2::splat(-reach), Vec2::splat(reach))
+        .clamp_length_max(reach);
+    let steps = (motion.length() / max_step).ceil().clamp(1.0, MAX_STEPS);
+    let step = motion / steps;
+    let floor = tile_height(map, from.x.floor() as i64, from.y.floor() as i64);
+    let mut position = from;
+    for _ in 0..steps as usize {
+        position = push_out(map, body, floor, position + step);
+    }
+    position
+}
+
+/// Pushes a circle at `position` out of every tile it shouldn't overlap.
+fn push_out(map: &Map, body: &Body, floor: f32, mut position: Vec2) -> Vec2 {
+    // Resolving the deepest overlap first keeps the seams between wall tiles from
+    // catching the body, a few passes settle corners
+    for _ in 0..4 {
+        match deepest_overlap(map, body, floor, position) {
+            Some(push) => position += push,
+            None => break,
+        }
+    }
+    position
+}
+
+/// The push out of the tile the circle overlaps the most, if it overlaps any.
+fn deepest_overlap(map: &Map, body: &Body, floor: f32, position: Vec2) -> Option<Vec2> {
+    let radius = body.radius;
+    let min = (position - radius).floor();
+    let max = (position + radius).floor();
+    let mut deepest: Option<(f32, Vec2)> = None;
+    for y in min.y as i64..=max.y as i64 {
+        for x in min.x as i64..=max.x as i64 {
+            if let Some(push) = segment_overlap(map, radius, x, y, position) {
+                if deepest.is_none_or(|(d, _)| push.length() > d) {
+                    deepest = Some((push.length(), push));
+                }
+                continue;
+            }
+            let height = tile_height(map, x, y);
+            if height.is_finite() && height - floor.min(height) <= body.step_height {
+                continue;
+            }
+            let low = Vec2::new(x as f32, y as f32);
+            let high = low + 1.0;
+            let offset = position - position.clamp(low, high);
+            let distance = offset.length();
+            let (depth, normal) = if distance > f32::EPSILON {
+                (radius - distance, offset / distance)
+            } else {
+                // The center is inside the tile, leave through the closest face
+                let faces = [
+                    (position.x - low.x, Vec2::new(-1.0, 0.0)),
+                    (high.x - position.x, Vec2::new(1.0, 0.0)),
+                    (position.y - low.y, Vec2::new(0.0, -1.0)),
+                    (high.y - position.y, Vec2::new(0.0, 1.0)),
+                ];
+                let (inside, normal) =
+                    faces
+                        .iter()
+                        .copied()
+                        .fold(faces[0], |a, b| if b.0 < a.0 { b } else { a });
+                (inside + radius, normal)
+            };
+            if depth > 0.0 && deepest.is_none_or(|(d, _)| depth > d) {
+                deepest = Some((depth, normal * depth));
+            }
+        }
+    }
+    deepest.map(|(_, push)| push)
+}
+
+/// The push out of the segment of tile `(x, y)`, if it has one the circle overlaps.
+fn segment_overlap(map: &Map, radius: f32, x: i64, y: i64, position: Vec2) -> Option<Vec2> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    let segment = map.segment(map.get(x as usize, y as usize)?)?;
+    let corner = Vec2::new(x as f32, y as f32);
+    let offset = position - (corner + segment.closest(position - corner));
+    let distance = offset.length();
+    let normal = match distance > f32::EPSILON {
+        true => offset / distance,
+        // Right on the line, any side will do
+        false => (segment.to - segment.from).perp().normalize_or_zero(),
+    };
+    Some(normal * (radius - distance)).filter(|_| distance < radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        map::Segment,
+        testing::{floor_point, random_map, Rng},
+    };
+
+    const BODY: Body = Body {
+        radius: 0.25,
+        step_height: 0.0,
+    };
+
+    fn map(rows: &[&str]) -> Map {
+        let tiles: Vec<char> = rows.iter().flat_map(|row| row.chars()).collect();
+        Map::new(rows[0].len(), rows.len(), tiles)
+    }
+
+    fn room() -> Map {
+        map(&["#####", "#...#", "#...#", "#...#", "#####"])
+    }
+
+    #[test]
+    fn moves_freely_in_the_open() {
+        let to = slide(&room(), &BODY, Vec2::new(2.0, 2.0), Vec2::new(0.5, -0.25));
+        assert!((to - Vec2::new(2.5, 1.75)).length() < 1e-5);
+    }
+
+    #[test]
+    fn stops_at_a_wall() {
+        let to = slide(&room(), &BODY, Vec2::new(2.0, 2.0), Vec2::new(5.0, 0.0));
+        assert!((to.x - (4.0 - BODY.radius)).abs() < 1e-4, "{:?}", to);
+        assert!((to.y - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn slides_along_a_wall() {
+        let to = slide(&room(), &BODY, Vec2::new(3.5, 2.0), Vec2::new(1.0, 0.5));
+        assert!((to.x - (4.0 - BODY.radius)).abs() < 1e-4, "{:?}", to);
+        assert!((to.y - 2.5).abs() < 1e-4, "{:?}", to);
+    }
+
+    #[test]
+    fn settles_in_an_inside_corner() {
+        let to = slide(&room(), &BODY, Vec2::new(2.0, 2.0), Vec2::new(3.0, 3.0));
+        let corner = Vec2::new(4.0, 4.0) - BODY.radius;
+        assert!((to - corner).length() < 1e-3, "{:?}", to);
+    }
+
+    #[test]
+    fn rounds_an_outside_corner() {
+        let map = map(&["#####", "#...#", "#.#.#", "#...#", "#####"]);
+        let from = Vec2::new(1.5, 1.5);
+        let to = slide(&map, &BODY, from, Vec2::new(1.0, 1.0));
+        let corner = Vec2::new(2.0, 2.0);
+        assert!((to - corner).length() >= BODY.radius - 1e-4, "{:?}", to);
+    }
+
+    #[test]
+    fn does_not_tunnel_through_thin_walls() {
+        let map = map(&["#######", "#..#..#", "#######"]);
+        let to = slide(&map, &BODY, Vec2::new(1.5, 1.5), Vec2::new(100.0, 0.0));
+        assert!(to.x < 3.0, "{:?}", to);
+    }
+
+    #[test]
+    fn does_not_tunnel_with_a_tiny_radius() {
+        let map = map(&["#######", "#..#..#", "#######"]);
+        let body = Body {
+            radius: 0.0,
+            ..BODY
+        };
+        let to = slide(&map, &body, Vec2::new(1.5, 1.5), Vec2::new(100.0, 0.0));
+        assert!(to.x <= 3.0, "{:?}", to);
+    }
+
+    #[test]
+    fn stops_at_a_segment() {
+        let thin = Segment::new(Vec2::new(0.5, 0.0), Vec2::new(0.5, 1.0));
+        let map = map(&["#####", "#.|.#", "#####"]).with_solid(&['#', '|']);
+        let map = map.with_segments(&[('|', thin)]);
+        let to = slide(&map, &BODY, Vec2::new(1.5, 1.5), Vec2::new(5.0, 0.0));
+        assert!((to.x - (2.5 - BODY.radius)).abs() < 1e-4, "{:?}", to);
+    }
+
+    #[test]
+    fn ignores_motions_that_are_not_finite() {
+        let from = Vec2::new(2.0, 2.0);
+        for motion in [
+            Vec2::new(f32::INFINITY, 0.0),
+            Vec2::new(0.0, f32::NEG_INFINITY),
+            Vec2::new(f32::NAN, 1.0),
+        ] {
+            assert_eq!(slide(&room(), &BODY, from, motion), from);
+        }
+    }
+
+    #[test]
+    fn huge_motions_still_end_inside_the_room() {
+        let to = slide(&room(), &BODY, Vec2::new(2.0, 2.0), Vec2::new(1e30, -1e30));
+        assert!(
+            (1.0..=4.0).contains(&to.x) && (1.0..=4.0).contains(&to.y),
+            "{:?}",
+            to
+        );
+    }
+
+    #[test]
+    fn never_leaves_the_map() {
+        let map = map(&["...", "...", "..."]);
+        let to = slide(&map, &BODY, Vec2::new(1.5, 1.5), Vec2::new(-10.0, 10.0));
+        assert!(
+            to.x >= BODY.radius - 1e-4 && to.y <= 3.0 - BODY.radius + 1e-4,
+            "{:?}",
+            to
+        );
+    }
+
+    #[test]
+    fn never_ends_a_tick_inside_a_wall() {
+        let mut rng = Rng::new(0x5eed);
+        for case in 0..200 {
+            let map = random_map(&mut rng, 12, 12, 0.3);
+            let point = match floor_point(&mut rng, &map) {
+                Some(point) => point,
+                None => continue,
+            };
+            let tile = point.floor();
+            let mut position = point.clamp(tile + BODY.radius, tile + 1.0 - BODY.radius);
+            for tick in 0..50 {
+                let motion = rng.direction() * rng.range(0.0, 2.0);
+                position = slide(&map, &BODY, position, motion);
+                assert!(
+                    !map.is_wall(position.x, position.y),
+                    "case {}, tick {}: in a wall at {:?}",
+                    case,
+                    tick,
+                    position
+                );
+            }
+        }
+    }
+}
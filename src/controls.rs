@@ -0,0 +1,224 @@
+use crate::paths;
+use olc_fps::{input::Actions, keys};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Keys the game reads directly rather than through actions, which can't be bound to any.
+pub const RESERVED: [i32; 16] = [
+    keys::ESCAPE,
+    keys::F1,
+    keys::F2,
+    keys::F5,
+    keys::F6,
+    keys::F9,
+    keys::F12,
+    // Picking a choice in a dialogue
+    b'1' as i32,
+    b'2' as i32,
+    b'3' as i32,
+    b'4' as i32,
+    b'5' as i32,
+    b'6' as i32,
+    b'7' as i32,
+    b'8' as i32,
+    b'9' as i32,
+];
+
+/// Where rebound keys are kept, next to the config file.
+pub fn path() -> PathBuf {
+    paths::config_dir().join("bindings.txt")
+}
+
+/// Everything the player can do during gameplay.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Forward,
+    Back,
+    /// Moves faster for as long as stamina lasts.
+    Sprint,
+    Crouch,
+    TurnLeft,
+    TurnRight,
+    /// Reads the sign in front of the player.
+    Interact,
+    /// Moves dialogues along and skips cutscenes.
+    Confirm,
+    RearView,
+    Cameras,
+    Palette,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Forward,
+        Action::Back,
+        Action::Sprint,
+        Action::Crouch,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Interact,
+        Action::Confirm,
+        Action::RearView,
+        Action::Cameras,
+        Action::Palette,
+    ];
+
+    /// How it's written in the bindings file.
+    pub fn key(self) -> &'static str {
+        match self {
+            Action::Forward => "forward",
+            Action::Back => "back",
+            Action::Sprint => "sprint",
+            Action::Crouch => "crouch",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::Interact => "interact",
+            Action::Confirm => "confirm",
+            Action::RearView => "rear_view",
+            Action::Cameras => "cameras",
+            Action::Palette => "palette",
+        }
+    }
+
+    /// Key into the `Locale`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Forward => "action.forward",
+            Action::Back => "action.back",
+            Action::Sprint => "action.sprint",
+            Action::Crouch => "action.crouch",
+            Action::TurnLeft => "action.turn_left",
+            Action::TurnRight => "action.turn_right",
+            Action::Interact => "action.interact",
+            Action::Confirm => "action.confirm",
+            Action::RearView => "action.rear_view",
+            Action::Cameras => "action.cameras",
+            Action::Palette => "action.palette",
+        }
+    }
+}
+
+/// The keys each action starts out bound to.
+pub fn default_actions() -> Actions<Action> {
+    Actions::new()
+        .with_binding(Action::Forward, 'W' as i32)
+        .with_binding(Action::Back, 'S' as i32)
+        .with_binding(Action::Sprint, keys::SHIFT)
+        .with_binding(Action::Crouch, keys::CONTROL)
+        .with_binding(Action::TurnLeft, 'A' as i32)
+        .with_binding(Action::TurnRight, 'D' as i32)
+        .with_binding(Action::Interact, 'E' as i32)
+        .with_binding(Action::Confirm, keys::SPACE)
+        .with_binding(Action::Confirm, keys::RETURN)
+        .with_binding(Action::RearView, 'R' as i32)
+        .with_binding(Action::Cameras, 'C' as i32)
+        .with_binding(Action::Palette, keys::F3)
+}
+
+pub fn load(path: &Path) -> Actions<Action> {
+    parse(&paths::read(path))
+}
+
+/// The default keys, with the actions the bindings file rebinds using its own instead. Its
+/// lines look like `confirm = SPACE, ENTER`, and ones that can't be read are skipped.
+pub fn parse(source: &str) -> Actions<Action> {
+    let mut actions = default_actions();
+    for line in source.lines() {
+        let (name, keys) = match line.split_once('=') {
+            Some((name, keys)) => (name.trim(), keys),
+            None => continue,
+        };
+        let action = Action::ALL.iter().find(|action| action.key() == name);
+        let keys: Option<Vec<i32>> = keys.split(',').map(|key| parse_key(key.trim())).collect();
+        // A hand-edited file can't take over the keys the game reads itself either
+        let keys = keys.map(|mut keys| {
+            keys.retain(|key| !RESERVED.contains(key));
+            keys
+        });
+        if let (Some(action), Some(keys)) = (action, keys.filter(|keys| !keys.is_empty())) {
+            let old: Vec<i32> = actions.keys(*action).collect();
+            for key in old {
+                actions.unbind(*action, key);
+            }
+            for key in keys {
+                actions.bind(*action, key);
+            }
+        }
+    }
+    actions
+}
+
+pub fn save(path: &Path, actions: &Actions<Action>) -> io::Result<()> {
+    let mut contents = String::new();
+    for action in Action::ALL.iter() {
+        let keys: Vec<String> = actions.keys(*action).map(key_name).collect();
+        contents.push_str(&format!("{} = {}\n", action.key(), keys.join(", ")));
+    }
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    paths::write(path, &contents)
+}
+
+/// What binding a key to an action took from another one.
+pub enum Conflict {
+    /// The key was one of several of `from`'s.
+    Taken { from: Action },
+    /// The key was the only one of `with`'s, which got the action's old key instead.
+    Swapped { with: Action, key: i32 },
+}
+
+/// Makes `key` the only one bound to `action`. An action that had it loses it, and gets
+/// the old key of `action` if it would otherwise be left without any.
+pub fn rebind(actions: &mut Actions<Action>, action: Action, key: i32) -> Option<Conflict> {
+    let old: Vec<i32> = actions.keys(action).collect();
+    for old_key in &old {
+        actions.unbind(action, *old_key);
+    }
+    actions.bind(action, key);
+    let other = *Action::ALL
+        .iter()
+        .find(|other| **other != action && actions.keys(**other).any(|k| k == key))?;
+    actions.unbind(other, key);
+    if actions.keys(other).next().is_some() {
+        return Some(Conflict::Taken { from: other });
+    }
+    let swapped = old.first().copied().filter(|old_key| *old_key != key)?;
+    actions.bind(other, swapped);
+    Some(Conflict::Swapped {
+        with: other,
+        key: swapped,
+    })
+}
+
+/// How a key is written on the keyboard, like `W`, `SPACE` or `F3`.
+pub fn key_name(key: i32) -> String {
+    let named = [
+        (keys::BACK, "BACKSPACE"),
+        (keys::TAB, "TAB"),
+        (keys::RETURN, "ENTER"),
+        (keys::SHIFT, "SHIFT"),
+        (keys::CONTROL, "CTRL"),
+        (keys::ESCAPE, "ESC"),
+        (keys::SPACE, "SPACE"),
+        (keys::LEFT, "LEFT"),
+        (keys::UP, "UP"),
+        (keys::RIGHT, "RIGHT"),
+        (keys::DOWN, "DOWN"),
+    ];
+    if let Some((_, name)) = named.iter().find(|(k, _)| *k == key) {
+        return name.to_string();
+    }
+    match key as u8 {
+        c @ b'A'..=b'Z' | c @ b'0'..=b'9' => (c as char).to_string(),
+        _ if (keys::F1..=keys::F12).contains(&key) => format!("F{}", key - keys::F1 + 1),
+        _ => format!("KEY {:#04x}", key),
+    }
+}
+
+/// The key `name` stands for, as written by [`key_name`].
+fn parse_key(name: &str) -> Option<i32> {
+    (keys::BACK..256).find(|key| key_name(*key).eq_ignore_ascii_case(name))
+}
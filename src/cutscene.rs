@@ -0,0 +1,107 @@
+use crate::{Camera, FOV, SCREEN_HEIGHT, SCREEN_WIDTH};
+use olc_fps::{
+    math::{lerp_angle, Vec2},
+    Locale, Palette, Screen,
+};
+
+/// Rows covered by each of the black bars at the top and bottom of the screen.
+const LETTERBOX: usize = 5;
+
+/// Where the camera is at a given time since the start of the cutscene.
+pub struct Keyframe {
+    pub time: f32,
+    pub position: Vec2,
+    pub angle: f32,
+}
+
+/// `text` is a key into the `Locale` the cutscene is drawn with.
+pub struct Caption {
+    pub start: f32,
+    pub end: f32,
+    pub text: &'static str,
+}
+
+pub struct Cutscene {
+    /// Sorted by time, the cutscene ends on the last one.
+    pub keyframes: Vec<Keyframe>,
+    pub captions: Vec<Caption>,
+}
+
+/// Plays a cutscene from its start.
+pub struct CutscenePlayer {
+    cutscene: Cutscene,
+    elapsed: f32,
+    cuts: bool,
+}
+
+impl CutscenePlayer {
+    pub fn new(cutscene: Cutscene) -> Self {
+        CutscenePlayer {
+            cutscene,
+            elapsed: 0.0,
+            cuts: false,
+        }
+    }
+
+    /// Holds the camera on each keyframe then cuts to the next, instead of panning.
+    pub fn with_cuts(mut self, cuts: bool) -> Self {
+        self.cuts = cuts;
+        self
+    }
+
+    /// Returns false once the last keyframe has been reached.
+    pub fn update(&mut self, delta_time: f32) -> bool {
+        self.elapsed += delta_time;
+        match self.cutscene.keyframes.last() {
+            Some(last) => self.elapsed < last.time,
+            None => false,
+        }
+    }
+
+    /// The camera interpolated between the keyframes around the current time.
+    pub fn camera(&self) -> Camera {
+        let keyframes = &self.cutscene.keyframes;
+        let next = keyframes
+            .iter()
+            .position(|k| k.time > self.elapsed)
+            .unwrap_or(keyframes.len() - 1);
+        let from = &keyframes[next.saturating_sub(1)];
+        let to = &keyframes[next];
+        let t = if self.cuts {
+            (self.elapsed >= to.time) as u8 as f32
+        } else if to.time > from.time {
+            ((self.elapsed - from.time) / (to.time - from.time)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        Camera {
+            position: from.position.lerp(to.position, t),
+            angle: lerp_angle(from.angle, to.angle, t),
+            fov: FOV,
+            pitch: 0.0,
+        }
+    }
+
+    /// Draws the letterbox bars and the current caption over the rendered scene.
+    pub fn draw(&self, screen: &mut Screen, locale: &Locale, palette: &Palette) {
+        let (width, bar) = (SCREEN_WIDTH as i32, LETTERBOX as i32);
+        screen.fill_rect(0, 0, width, bar, ' ', palette.background);
+        screen.fill_rect(
+            0,
+            SCREEN_HEIGHT as i32 - bar,
+            width,
+            bar,
+            ' ',
+            palette.background,
+        );
+        let caption = self
+            .cutscene
+            .captions
+            .iter()
+            .find(|c| c.start <= self.elapsed && self.elapsed < c.end);
+        if let Some(caption) = caption {
+            let y = (SCREEN_HEIGHT - LETTERBOX / 2 - 1) as i32;
+            screen.centered_string(y, locale.get(caption.text), palette.text);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::f32::consts::TAU;
+
+/// Where the sun is, as a fraction of a day from midnight, so `0.5` is noon.
+pub struct Daylight {
+    time_of_day: f32,
+    /// Seconds a whole day takes, or `None` to stay at the same time.
+    day_length: Option<f32>,
+}
+
+impl Daylight {
+    /// Starts in the morning.
+    pub fn new(day_length: Option<f32>) -> Self {
+        Daylight {
+            time_of_day: 0.3,
+            day_length,
+        }
+    }
+
+    /// Stays at `time_of_day` instead of following the day cycle, when there's one.
+    pub fn with_locked(mut self, time_of_day: Option<f32>) -> Self {
+        if let Some(time_of_day) = time_of_day {
+            self.time_of_day = time_of_day.rem_euclid(1.0);
+            self.day_length = None;
+        }
+        self
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        if let Some(length) = self.day_length {
+            self.time_of_day = (self.time_of_day + delta_time / length).rem_euclid(1.0);
+        }
+    }
+
+    /// From 0 at midnight to 1 at noon.
+    pub fn light(&self) -> f32 {
+        0.5 - 0.5 * (self.time_of_day * TAU).cos()
+    }
+
+    /// How many shades darker than in broad daylight things look, up to 2 at night.
+    pub fn darkness(&self) -> u32 {
+        match self.light() {
+            light if light > 0.6 => 0,
+            light if light > 0.25 => 1,
+            _ => 2,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use olc_fps::{Align, Border, Locale, Palette, Screen};
+
+/// Tile character used for readable signs on the map.
+pub const SIGN: char = 'S';
+
+const BOX_HEIGHT: usize = 9;
+const BOX_MARGIN: usize = 10;
+
+/// Texts are keys into the `Locale` the dialogue is drawn with.
+pub struct Choice {
+    pub label: String,
+    /// Page to jump to, or `None` to close the dialogue.
+    pub next: Option<usize>,
+}
+
+pub struct Page {
+    pub text: String,
+    /// When empty, any confirm key moves on to the following page.
+    pub choices: Vec<Choice>,
+}
+
+pub struct Sign {
+    pub x: usize,
+    pub y: usize,
+    pub pages: Vec<Page>,
+}
+
+impl Sign {
+    /// Reads a sign from a map's `@sign` line: its tile, then its pages split by `|`. A page
+    /// is its text followed by its choices, each a label with `>` and the page it leads to,
+    /// counted from 1, or without to close the sign, like
+    /// `@sign 11 4 sign.welcome | sign.hint sign.yes>3 sign.no | sign.wall`.
+    pub fn parse(value: &str) -> Option<Sign> {
+        let mut words = value.split_whitespace();
+        let x = words.next()?.parse().ok()?;
+        let y = words.next()?.parse().ok()?;
+        let pages = words.collect::<Vec<_>>().join(" ");
+        let pages = pages.split('|').map(|page| {
+            let mut words = page.split_whitespace();
+            let text = words.next()?.to_string();
+            let choices = words.map(|choice| {
+                let (label, next) = match choice.split_once('>') {
+                    Some((label, page)) => {
+                        (label, Some(page.parse::<usize>().ok()?.checked_sub(1)?))
+                    }
+                    None => (choice, None),
+                };
+                let label = label.to_string();
+                Some(Choice { label, next })
+            });
+            let choices = choices.collect::<Option<_>>()?;
+            Some(Page { text, choices })
+        });
+        let pages = pages.collect::<Option<_>>()?;
+        Some(Sign { x, y, pages })
+    }
+}
+
+/// The page of a sign currently being read.
+pub struct Dialogue {
+    sign: usize,
+    page: usize,
+}
+
+pub enum Input {
+    Continue,
+    Choose(usize),
+}
+
+impl Dialogue {
+    pub fn open(signs: &[Sign], x: usize, y: usize) -> Option<Self> {
+        let sign = signs.iter().position(|s| s.x == x && s.y == y)?;
+        Some(Dialogue { sign, page: 0 })
+    }
+
+    /// Returns `None` once the dialogue is over.
+    pub fn advance(self, signs: &[Sign], input: Input) -> Option<Self> {
+        let pages = &signs[self.sign].pages;
+        let page = &pages[self.page];
+        let next = match input {
+            Input::Continue if page.choices.is_empty() => Some(self.page + 1),
+            Input::Continue => return Some(self),
+            Input::Choose(index) => match page.choices.get(index) {
+                Some(choice) => choice.next,
+                None => return Some(self),
+            },
+        };
+        match next {
+            Some(page) if page < pages.len() => Some(Dialogue { page, ..self }),
+            _ => None,
+        }
+    }
+
+    /// Draws the current page in a framed box over the bottom of the screen.
+    pub fn draw(&self, screen: &mut Screen, signs: &[Sign], locale: &Locale, palette: &Palette) {
+        let page = &signs[self.sign].pages[self.page];
+        let (left, top) = (BOX_MARGIN as i32, (SCREEN_HEIGHT - BOX_HEIGHT - 1) as i32);
+        let (width, height) = ((SCREEN_WIDTH - 2 * BOX_MARGIN) as i32, BOX_HEIGHT as i32);
+        let text_color = palette.text;
+
+        screen.fill_rect(left, top, width, height, ' ', text_color);
+        screen.frame(left, top, width, height, &Border::DOUBLE, palette.frame);
+
+        // Keep room under the text for the choices, or the prompt to carry on
+        let (inner_left, inner_top) = (left + 2, top + 1);
+        let (inner_width, inner_height) = (width - 4, height - 2);
+        let text_height = inner_height - page.choices.len().max(1) as i32;
+        let mut y = inner_top
+            + screen.text_box(
+                inner_left,
+                inner_top,
+                inner_width,
+                text_height,
+                locale.get(&page.text),
+                text_color,
+            );
+        for (i, choice) in page.choices.iter().enumerate() {
+            let label = format!("{}) {}", i + 1, locale.get(&choice.label));
+            screen.string(inner_left, y, &label, text_color);
+            y += 1;
+        }
+        if page.choices.is_empty() {
+            let (x, width) = (inner_left, inner_width);
+            let prompt = locale.get("dialogue.continue");
+            screen.aligned_string(x, y, width, prompt, Align::Right, text_color);
+        }
+    }
+}
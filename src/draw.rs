@@ -0,0 +1,544 @@
+//! Safe drawing primitives over a grid of character cells.
+//!
+//! Everything is clipped to the screen, so callers never index the buffers themselves.
+
+/// Windows console character attributes, combine a foreground and a background with `|`.
+pub mod color {
+    pub const FG_BLACK: u16 = 0x0000;
+    pub const FG_DARK_BLUE: u16 = 0x0001;
+    pub const FG_DARK_GREEN: u16 = 0x0002;
+    pub const FG_DARK_CYAN: u16 = 0x0003;
+    pub const FG_DARK_RED: u16 = 0x0004;
+    pub const FG_DARK_MAGENTA: u16 = 0x0005;
+    pub const FG_DARK_YELLOW: u16 = 0x0006;
+    pub const FG_GREY: u16 = 0x0007;
+    pub const FG_DARK_GREY: u16 = 0x0008;
+    pub const FG_BLUE: u16 = 0x0009;
+    pub const FG_GREEN: u16 = 0x000A;
+    pub const FG_CYAN: u16 = 0x000B;
+    pub const FG_RED: u16 = 0x000C;
+    pub const FG_MAGENTA: u16 = 0x000D;
+    pub const FG_YELLOW: u16 = 0x000E;
+    pub const FG_WHITE: u16 = 0x000F;
+    pub const BG_BLACK: u16 = 0x0000;
+    pub const BG_DARK_BLUE: u16 = 0x0010;
+    pub const BG_DARK_GREEN: u16 = 0x0020;
+    pub const BG_DARK_CYAN: u16 = 0x0030;
+    pub const BG_DARK_RED: u16 = 0x0040;
+    pub const BG_DARK_MAGENTA: u16 = 0x0050;
+    pub const BG_DARK_YELLOW: u16 = 0x0060;
+    pub const BG_GREY: u16 = 0x0070;
+    pub const BG_DARK_GREY: u16 = 0x0080;
+    pub const BG_BLUE: u16 = 0x0090;
+    pub const BG_GREEN: u16 = 0x00A0;
+    pub const BG_CYAN: u16 = 0x00B0;
+    pub const BG_RED: u16 = 0x00C0;
+    pub const BG_MAGENTA: u16 = 0x00D0;
+    pub const BG_YELLOW: u16 = 0x00E0;
+    pub const BG_WHITE: u16 = 0x00F0;
+
+    /// What the console uses when nothing else is asked for.
+    pub const DEFAULT: u16 = FG_GREY | BG_BLACK;
+}
+
+/// Where a string sits within the width it's given.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// The characters used to draw a frame.
+pub struct Border {
+    pub horizontal: char,
+    pub vertical: char,
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+}
+
+impl Border {
+    pub const ASCII: Border = Border {
+        horizontal: '-',
+        vertical: '|',
+        top_left: '+',
+        top_right: '+',
+        bottom_left: '+',
+        bottom_right: '+',
+    };
+    pub const SINGLE: Border = Border {
+        horizontal: '\u{2500}',
+        vertical: '\u{2502}',
+        top_left: '\u{250C}',
+        top_right: '\u{2510}',
+        bottom_left: '\u{2514}',
+        bottom_right: '\u{2518}',
+    };
+    pub const DOUBLE: Border = Border {
+        horizontal: '\u{2550}',
+        vertical: '\u{2551}',
+        top_left: '\u{2554}',
+        top_right: '\u{2557}',
+        bottom_left: '\u{255A}',
+        bottom_right: '\u{255D}',
+    };
+}
+
+/// Splits `text` into lines of at most `width` characters, breaking between words.
+/// Words longer than a whole line are cut wherever they hit the edge.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut len = 0;
+        for word in paragraph.split_whitespace() {
+            let word_len = word.chars().count();
+            if len > 0 && len + 1 + word_len > width {
+                lines.push(std::mem::take(&mut line));
+                len = 0;
+            }
+            if len > 0 {
+                line.push(' ');
+                len += 1;
+            }
+            for c in word.chars() {
+                if len == width {
+                    lines.push(std::mem::take(&mut line));
+                    len = 0;
+                }
+                line.push(c);
+                len += 1;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// The whole number whose square is closest to `square`.
+fn nearest_root(square: i64) -> i64 {
+    // The float is only a first guess this far up
+    let mut root = (square as f64).sqrt() as i64;
+    while root * root > square {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= square {
+        root += 1;
+    }
+    match square - root * root > (root + 1) * (root + 1) - square {
+        true => root + 1,
+        false => root,
+    }
+}
+
+/// UTF-16 characters and their color attributes, stored row by row.
+pub struct Screen {
+    width: usize,
+    height: usize,
+    cells: Vec<u16>,
+    colors: Vec<u16>,
+}
+
+impl Screen {
+    pub fn new(width: usize, height: usize) -> Self {
+        Screen {
+            width,
+            height,
+            cells: vec![' ' as u16; width * height],
+            colors: vec![color::DEFAULT; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cells(&self) -> &[u16] {
+        &self.cells
+    }
+
+    pub fn colors(&self) -> &[u16] {
+        &self.colors
+    }
+
+    pub fn clear(&mut self, c: char, color: u16) {
+        self.cells.fill(c as u16);
+        self.colors.fill(color);
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            Some(y as usize * self.width + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The character at `(x, y)`, if it's on the screen.
+    pub fn get(&self, x: i32, y: i32) -> Option<char> {
+        self.index(x, y)
+            .and_then(|i| std::char::from_u32(self.cells[i] as u32))
+    }
+
+    pub fn set_cell(&mut self, x: i32, y: i32, c: char, color: u16) {
+        if let Some(i) = self.index(x, y) {
+            self.cells[i] = c as u16;
+            self.colors[i] = color;
+        }
+    }
+
+    /// Draws from `x1` to `x2` inclusive.
+    pub fn hline(&mut self, x1: i32, x2: i32, y: i32, c: char, color: u16) {
+        if y < 0 || y >= self.height as i32 {
+            return;
+        }
+        for x in x1.min(x2).max(0)..=x1.max(x2).min(self.width as i32 - 1) {
+            self.set_cell(x, y, c, color);
+        }
+    }
+
+    /// Draws from `y1` to `y2` inclusive.
+    pub fn vline(&mut self, x: i32, y1: i32, y2: i32, c: char, color: u16) {
+        if x < 0 || x >= self.width as i32 {
+            return;
+        }
+        for y in y1.min(y2).max(0)..=y1.max(y2).min(self.height as i32 - 1) {
+            self.set_cell(x, y, c, color);
+        }
+    }
+
+    /// Draws a line between both points, inclusive, with Bresenham's algorithm.
+    pub fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, c: char, color: u16) {
+        let (x1, y1, x2, y2) = match self.clip(x1, y1, x2, y2) {
+            Some(ends) => ends,
+            None => return,
+        };
+        let (dx, dy) = ((x2 - x1).abs(), -(y2 - y1).abs());
+        let (step_x, step_y) = ((x2 - x1).signum(), (y2 - y1).signum());
+        let (mut x, mut y, mut error) = (x1, y1, dx + dy);
+        loop {
+            self.set_cell(x, y, c, color);
+            if x == x2 && y == y2 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// The ends of the part of the line between both points that's on the screen, with the
+    /// Liang-Barsky algorithm. Ends already on the screen stay the same.
+    fn clip(&self, x1: i32, y1: i32, x2: i32, y2: i32) -> Option<(i32, i32, i32, i32)> {
+        let (max_x, max_y) = (self.width as f64 - 1.0, self.height as f64 - 1.0);
+        let (x, y) = (x1 as f64, y1 as f64);
+        let (dx, dy) = (x2 as f64 - x, y2 as f64 - y);
+        // Narrow down the stretch of `t` in `(x + t * dx, y + t * dy)` inside each edge
+        let (mut from, mut to) = (0.0_f64, 1.0_f64);
+        for (p, q) in [(-dx, x), (dx, max_x - x), (-dy, y), (dy, max_y - y)] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return None;
+                }
+            } else if p < 0.0 {
+                from = from.max(q / p);
+            } else {
+                to = to.min(q / p);
+            }
+        }
+        if from > to {
+            return None;
+        }
+        let at = |t: f64| ((x + t * dx).round() as i32, (y + t * dy).round() as i32);
+        let ((x1, y1), (x2, y2)) = (at(from), at(to));
+        Some((x1, y1, x2, y2))
+    }
+
+    /// Outlines the `width` by `height` rectangle whose top left corner is `(x, y)`.
+    pub fn rect(&mut self, x: i32, y: i32, width: i32, height: i32, c: char, color: u16) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (right, bottom) = (x.saturating_add(width - 1), y.saturating_add(height - 1));
+        self.hline(x, right, y, c, color);
+        self.hline(x, right, bottom, c, color);
+        self.vline(x, y, bottom, c, color);
+        self.vline(right, y, bottom, c, color);
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, c: char, color: u16) {
+        for row in y.max(0)..y.saturating_add(height).min(self.height as i32) {
+            for col in x.max(0)..x.saturating_add(width).min(self.width as i32) {
+                self.set_cell(col, row, c, color);
+            }
+        }
+    }
+
+    /// Outlines a circle, one cell per column or row along each eighth of it, at the cell
+    /// nearest to the circle. Only the stretches that can land on the screen are worked out,
+    /// so a huge circle costs no more than a small one.
+    pub fn circle(&mut self, cx: i32, cy: i32, radius: i32, c: char, color: u16) {
+        if radius < 0 {
+            return;
+        }
+        // Wide enough that the sums and squares below can't overflow
+        let (cx, cy, radius) = (cx as i64, cy as i64, radius as i64);
+        let (width, height) = (self.width as i64, self.height as i64);
+        // Offsets along an eighth that put one of its mirrored points in a column or row of
+        // the screen, from 0 up to the radius
+        let stretches = [
+            (-cx, width - 1 - cx),
+            (cx - width + 1, cx),
+            (-cy, height - 1 - cy),
+            (cy - height + 1, cy),
+        ];
+        let mut set = |x: i64, y: i64| {
+            if (0..width).contains(&x) && (0..height).contains(&y) {
+                self.set_cell(x as i32, y as i32, c, color);
+            }
+        };
+        for (from, to) in stretches.iter() {
+            for x in (*from).max(0)..=(*to).min(radius) {
+                let y = nearest_root(radius * radius - x * x);
+                if x > y {
+                    break;
+                }
+                for (dx, dy) in [(x, y), (y, x)] {
+                    set(cx + dx, cy + dy);
+                    set(cx - dx, cy + dy);
+                    set(cx + dx, cy - dy);
+                    set(cx - dx, cy - dy);
+                }
+            }
+        }
+    }
+
+    /// Draws `text` on a single row starting at `x`, dropping whatever falls off the screen.
+    pub fn string(&mut self, x: i32, y: i32, text: &str, color: u16) {
+        for (i, c) in text.chars().enumerate() {
+            let x = x.saturating_add(i as i32);
+            if x >= self.width as i32 {
+                break;
+            }
+            self.set_cell(x, y, c, color);
+        }
+    }
+
+    /// Draws `text` within the `width` columns starting at `x`, cutting off what doesn't fit.
+    pub fn aligned_string(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: i32,
+        text: &str,
+        align: Align,
+        color: u16,
+    ) {
+        let width = width.max(0);
+        let len = text.chars().count() as i32;
+        let offset = match align {
+            Align::Left => 0,
+            Align::Center => (width - len).max(0) / 2,
+            Align::Right => (width - len).max(0),
+        };
+        let text: String = text.chars().take(width as usize).collect();
+        self.string(x.saturating_add(offset), y, &text, color);
+    }
+
+    /// Centers `text` on row `y` of the whole screen.
+    pub fn centered_string(&mut self, y: i32, text: &str, color: u16) {
+        let width = self.width as i32;
+        self.aligned_string(0, y, width, text, Align::Center, color);
+    }
+
+    /// Word-wraps `text` inside a rectangle, dropping the lines that don't fit.
+    /// Returns how many rows were drawn.
+    pub fn text_box(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        text: &str,
+        color: u16,
+    ) -> i32 {
+        let lines = wrap(text, width.max(1) as usize);
+        let rows = lines.len().min(height.max(0) as usize);
+        for (row, line) in lines.iter().take(rows).enumerate() {
+            self.string(x, y.saturating_add(row as i32), line, color);
+        }
+        rows as i32
+    }
+
+    /// Outlines a rectangle with `border`, leaving its inside untouched.
+    pub fn frame(&mut self, x: i32, y: i32, width: i32, height: i32, border: &Border, color: u16) {
+        if width < 2 || height < 2 {
+            return;
+        }
+        let (right, bottom) = (x.saturating_add(width - 1), y.saturating_add(height - 1));
+        self.hline(x, right, y, border.horizontal, color);
+        self.hline(x, right, bottom, border.horizontal, color);
+        self.vline(x, y, bottom, border.vertical, color);
+        self.vline(right, y, bottom, border.vertical, color);
+        self.set_cell(x, y, border.top_left, color);
+        self.set_cell(right, y, border.top_right, color);
+        self.set_cell(x, bottom, border.bottom_left, color);
+        self.set_cell(right, bottom, border.bottom_right, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(screen: &Screen) -> Vec<String> {
+        let rows = screen.cells().chunks(screen.width());
+        rows.map(String::from_utf16_lossy).collect()
+    }
+
+    fn drawn(screen: &Screen) -> usize {
+        screen.cells().iter().filter(|c| **c == '*' as u16).count()
+    }
+
+    #[test]
+    fn lines_stay_on_the_screen() {
+        let mut screen = Screen::new(8, 4);
+        screen.hline(i32::MIN, i32::MAX, 1, '*', color::DEFAULT);
+        assert_eq!(drawn(&screen), 8);
+        screen.vline(2, -1_000_000_000, 1_000_000_000, '*', color::DEFAULT);
+        assert_eq!(drawn(&screen), 11);
+        screen.hline(-5, 20, 4, '*', color::DEFAULT);
+        screen.vline(-1, 0, 3, '*', color::DEFAULT);
+        assert_eq!(drawn(&screen), 11);
+
+        let mut screen = Screen::new(8, 4);
+        screen.line(i32::MIN, 3, i32::MAX, 3, '*', color::DEFAULT);
+        assert_eq!(drawn(&screen), 8);
+        screen.line(-4, -4, 11, 11, '*', color::DEFAULT);
+        assert_eq!(drawn(&screen), 11);
+        assert_eq!(screen.get(0, 0), Some('*'));
+        assert_eq!(screen.get(2, 2), Some('*'));
+        screen.line(-10, -10, -1, 20, '*', color::DEFAULT);
+        assert_eq!(drawn(&screen), 11);
+    }
+
+    #[test]
+    fn shapes_stay_on_the_screen() {
+        let mut screen = Screen::new(8, 4);
+        let (min, max) = (i32::MIN, i32::MAX);
+        screen.fill_rect(max - 1, max - 1, max, max, '*', color::DEFAULT);
+        screen.rect(max - 1, 0, max, max, '*', color::DEFAULT);
+        screen.frame(max - 1, 0, max, max, &Border::ASCII, color::DEFAULT);
+        screen.circle(max, max, max, '*', color::DEFAULT);
+        screen.circle(min, min, max, '*', color::DEFAULT);
+        screen.string(max - 1, 0, "**", color::DEFAULT);
+        screen.aligned_string(max - 1, 0, max, "**", Align::Right, color::DEFAULT);
+        screen.text_box(0, max - 1, 8, max, "* * * *", color::DEFAULT);
+        assert_eq!(drawn(&screen), 0);
+
+        screen.fill_rect(min, min, max, max, '*', color::DEFAULT);
+        assert_eq!(drawn(&screen), 0);
+        screen.fill_rect(-2, -2, max, max, '*', color::DEFAULT);
+        assert_eq!(drawn(&screen), 32);
+
+        let mut screen = Screen::new(8, 4);
+        screen.frame(-1, -1, 10, 6, &Border::ASCII, color::DEFAULT);
+        assert_eq!(
+            screen.cells().iter().filter(|c| **c != ' ' as u16).count(),
+            0
+        );
+        screen.rect(0, 0, max, max, '*', color::DEFAULT);
+        assert_eq!(drawn(&screen), 11);
+        // Covers the whole screen, so none of its outline is on it
+        screen.circle(4, 2, max, '#', color::DEFAULT);
+        assert!(!screen.cells().contains(&('#' as u16)));
+        screen.circle(3, 1, 1, '#', color::DEFAULT);
+        assert_eq!(screen.get(3, 2), Some('#'));
+    }
+
+    #[test]
+    fn circles_are_round() {
+        let mut screen = Screen::new(9, 9);
+        screen.circle(4, 4, 2, '*', color::DEFAULT);
+        assert_eq!(
+            rows(&screen)[1..8],
+            [
+                "         ",
+                "   ***   ",
+                "  *   *  ",
+                "  *   *  ",
+                "  *   *  ",
+                "   ***   ",
+                "         ",
+            ]
+        );
+    }
+
+    #[test]
+    fn wraps_between_words() {
+        assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+        assert_eq!(wrap("a  b   c", 3), ["a b", "c"]);
+        assert_eq!(wrap("one\n\ntwo", 10), ["one", "", "two"]);
+        assert_eq!(wrap("", 10), [""]);
+    }
+
+    #[test]
+    fn cuts_words_longer_than_a_line() {
+        assert_eq!(wrap("abcdefgh ij", 3), ["abc", "def", "gh", "ij"]);
+        assert_eq!(wrap("ab cdefg", 4), ["ab", "cdef", "g"]);
+        // No width still makes progress, a character at a time
+        assert_eq!(wrap("abc", 0), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn aligns_strings_within_their_width() {
+        let mut screen = Screen::new(10, 4);
+        screen.aligned_string(1, 0, 8, "left", Align::Left, color::DEFAULT);
+        screen.aligned_string(1, 1, 8, "mid", Align::Center, color::DEFAULT);
+        screen.aligned_string(1, 2, 8, "right", Align::Right, color::DEFAULT);
+        screen.aligned_string(1, 3, 4, "too long", Align::Right, color::DEFAULT);
+        assert_eq!(
+            rows(&screen),
+            [" left     ", "   mid    ", "    right ", " too      "]
+        );
+    }
+
+    #[test]
+    fn text_boxes_drop_what_does_not_fit() {
+        let mut screen = Screen::new(8, 4);
+        let drawn = screen.text_box(1, 1, 5, 2, "one two three", color::DEFAULT);
+        assert_eq!(drawn, 2);
+        assert_eq!(
+            rows(&screen),
+            ["        ", " one    ", " two    ", "        "]
+        );
+        assert_eq!(screen.text_box(0, 0, 5, 0, "hidden", color::DEFAULT), 0);
+        assert_eq!(screen.text_box(0, 0, 5, -3, "hidden", color::DEFAULT), 0);
+        assert_eq!(screen.text_box(0, 0, -5, 4, "ab", color::DEFAULT), 2);
+        assert_eq!(screen.get(0, 0), Some('a'));
+    }
+
+    #[test]
+    fn frames_too_small_for_their_corners_are_skipped() {
+        let mut screen = Screen::new(4, 4);
+        screen.frame(0, 0, 1, 4, &Border::ASCII, color::DEFAULT);
+        screen.frame(0, 0, 4, 1, &Border::ASCII, color::DEFAULT);
+        screen.frame(0, 0, -4, -4, &Border::ASCII, color::DEFAULT);
+        assert_eq!(rows(&screen), ["    "; 4]);
+        screen.frame(0, 0, 2, 2, &Border::ASCII, color::DEFAULT);
+        assert_eq!(rows(&screen), ["++  ", "++  ", "    ", "    "]);
+    }
+}
@@ -0,0 +1,351 @@
+#[cfg(windows)]
+use crate::win32::{self, InputBuffer, InputEvent, ScreenBuffer};
+use crate::{
+    draw::{color, Screen},
+    error::GameError,
+};
+#[cfg(not(windows))]
+use std::io;
+#[cfg(windows)]
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long each frame at least lasts while the console doesn't have the focus, there's no
+/// point in drawing an unseen game as fast as possible.
+#[cfg(windows)]
+const UNFOCUSED_FRAME_TIME: Duration = Duration::from_millis(50);
+
+/// Virtual-key codes for the keys that don't map to their own ASCII character.
+/// Letters and digits use their uppercase ASCII value, e.g. `'W' as i32`.
+pub mod keys {
+    pub const BACK: i32 = 0x08;
+    pub const TAB: i32 = 0x09;
+    pub const RETURN: i32 = 0x0D;
+    pub const SHIFT: i32 = 0x10;
+    pub const CONTROL: i32 = 0x11;
+    pub const ESCAPE: i32 = 0x1B;
+    pub const SPACE: i32 = 0x20;
+    pub const LEFT: i32 = 0x25;
+    pub const UP: i32 = 0x26;
+    pub const RIGHT: i32 = 0x27;
+    pub const DOWN: i32 = 0x28;
+    pub const F1: i32 = 0x70;
+    pub const F2: i32 = 0x71;
+    pub const F3: i32 = 0x72;
+    pub const F4: i32 = 0x73;
+    pub const F5: i32 = 0x74;
+    pub const F6: i32 = 0x75;
+    pub const F7: i32 = 0x76;
+    pub const F8: i32 = 0x77;
+    pub const F9: i32 = 0x78;
+    pub const F10: i32 = 0x79;
+    pub const F11: i32 = 0x7A;
+    pub const F12: i32 = 0x7B;
+}
+
+/// State of every virtual key, sampled once per frame.
+#[derive(Clone)]
+pub struct Keys {
+    held: [bool; 256],
+    previous: [bool; 256],
+}
+
+impl Keys {
+    fn new() -> Self {
+        Keys {
+            held: [false; 256],
+            previous: [false; 256],
+        }
+    }
+
+    /// Samples every key with `is_down`, remembering the last frame's state.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn update(&mut self, is_down: impl Fn(i32) -> bool) {
+        self.previous = self.held;
+        for (key, held) in self.held.iter_mut().enumerate() {
+            *held = is_down(key as i32);
+        }
+    }
+
+    pub fn held(&self, key: i32) -> bool {
+        self.held[key as usize & 0xff]
+    }
+
+    /// True only on the frame the key goes down.
+    pub fn pressed(&self, key: i32) -> bool {
+        let key = key as usize & 0xff;
+        self.held[key] && !self.previous[key]
+    }
+
+    /// True only on the frame the key goes up.
+    pub fn released(&self, key: i32) -> bool {
+        let key = key as usize & 0xff;
+        !self.held[key] && self.previous[key]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// Where the mouse is over the console, in cells, and the state of its buttons.
+#[derive(Clone, Copy, Default)]
+pub struct Mouse {
+    x: i32,
+    y: i32,
+    held: [bool; 3],
+    previous: [bool; 3],
+    wheel: i32,
+}
+
+impl Mouse {
+    /// Starts a new frame, with the buttons held as they were at the end of the last one.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn next_frame(&mut self) {
+        self.previous = self.held;
+        self.wheel = 0;
+    }
+
+    /// The cell under the mouse, as of the last time it moved over the console.
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    pub fn held(&self, button: MouseButton) -> bool {
+        self.held[button as usize]
+    }
+
+    /// True only on the frame the button goes down.
+    pub fn pressed(&self, button: MouseButton) -> bool {
+        self.held[button as usize] && !self.previous[button as usize]
+    }
+
+    /// True only on the frame the button goes up.
+    pub fn released(&self, button: MouseButton) -> bool {
+        !self.held[button as usize] && self.previous[button as usize]
+    }
+
+    /// Notches the wheel turned this frame, positive away from the user.
+    pub fn wheel(&self) -> i32 {
+        self.wheel
+    }
+}
+
+/// The screen buffer and input of a running game.
+///
+/// Drawing outside of the screen is clipped, so callers don't have to check bounds.
+/// The `Screen` returned by `screen_mut` has more primitives, with colors.
+pub struct Console {
+    screen: Screen,
+    keys: Keys,
+    mouse: Mouse,
+    focused: bool,
+    max_fps: Option<f32>,
+    /// Waiting to be shown in the title bar.
+    title: Option<String>,
+}
+
+impl Console {
+    pub fn new(width: usize, height: usize) -> Self {
+        Console {
+            screen: Screen::new(width, height),
+            keys: Keys::new(),
+            mouse: Mouse::default(),
+            focused: true,
+            max_fps: None,
+            title: None,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.screen.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.screen.height()
+    }
+
+    pub fn keys(&self) -> &Keys {
+        &self.keys
+    }
+
+    pub fn mouse(&self) -> &Mouse {
+        &self.mouse
+    }
+
+    /// False while another window has the keyboard focus, every key reads as up until
+    /// the console gets it back.
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Shows `title` in the window's title bar once the frame is done.
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.title = Some(title.into());
+    }
+
+    /// Sleeps between frames to stay under `max_fps`, e.g. to save power in menus.
+    /// `None`, or a rate that isn't positive, runs as fast as possible.
+    pub fn set_max_fps(&mut self, max_fps: Option<f32>) {
+        self.max_fps = max_fps.filter(|fps| *fps > 0.0);
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    pub fn screen_mut(&mut self) -> &mut Screen {
+        &mut self.screen
+    }
+
+    pub fn clear(&mut self, c: char) {
+        self.screen.clear(c, color::DEFAULT);
+    }
+
+    pub fn draw(&mut self, x: i32, y: i32, c: char) {
+        self.screen.set_cell(x, y, c, color::DEFAULT);
+    }
+
+    /// Draws `text` on a single row starting at `x`.
+    pub fn draw_string(&mut self, x: i32, y: i32, text: &str) {
+        self.screen.string(x, y, text, color::DEFAULT);
+    }
+
+    /// Fills the rectangle from `(x1, y1)` up to, but not including, `(x2, y2)`.
+    pub fn fill(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, c: char) {
+        self.screen
+            .fill_rect(x1, y1, x2 - x1, y2 - y1, c, color::DEFAULT);
+    }
+
+    /// Draws a line between both points, inclusive.
+    pub fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, c: char) {
+        self.screen.line(x1, y1, x2, y2, c, color::DEFAULT);
+    }
+}
+
+/// A game driven by [`run`], in the spirit of the olcConsoleGameEngine.
+///
+/// ```no_run
+/// use olc_fps::{keys, Console, ConsoleGameEngine};
+///
+/// struct Bounce {
+///     x: f32,
+///     speed: f32,
+/// }
+///
+/// impl ConsoleGameEngine for Bounce {
+///     fn on_update(&mut self, console: &mut Console, delta_time: f32) -> bool {
+///         self.x += self.speed * delta_time;
+///         if self.x < 0.0 || self.x >= console.width() as f32 {
+///             self.speed = -self.speed;
+///         }
+///         console.clear(' ');
+///         console.draw_string(0, 0, "Press ESC to quit");
+///         console.draw(self.x as i32, 10, 'O');
+///         !console.keys().pressed(keys::ESCAPE)
+///     }
+/// }
+///
+/// olc_fps::run(&mut Bounce { x: 0.0, speed: 20.0 }, 80, 25).unwrap();
+/// ```
+pub trait ConsoleGameEngine {
+    /// Called once before the first frame. Returning false quits right away.
+    fn on_create(&mut self, _console: &mut Console) -> bool {
+        true
+    }
+
+    /// Called every frame with the seconds elapsed since the previous one.
+    /// Returning false quits.
+    fn on_update(&mut self, console: &mut Console, delta_time: f32) -> bool;
+}
+
+/// Runs `game` in a new console screen buffer until it asks to quit.
+///
+/// Fails with [`GameError::Console`] when the screen buffer can't be set up or written to.
+#[cfg(windows)]
+pub fn run(
+    game: &mut impl ConsoleGameEngine,
+    width: usize,
+    height: usize,
+) -> Result<(), GameError> {
+    let buffer = ScreenBuffer::new().map_err(GameError::Console)?;
+    let input = InputBuffer::new().map_err(GameError::Console)?;
+
+    let mut console = Console::new(width, height);
+    if !game.on_create(&mut console) {
+        return Ok(());
+    }
+
+    let mut start;
+    let mut end = Instant::now();
+    loop {
+        start = Instant::now();
+        let delta_time = (start - end).as_secs_f32();
+        end = start;
+
+        console.mouse.next_frame();
+        for event in input.read().map_err(GameError::Console)? {
+            match event {
+                InputEvent::Focus(focused) => console.focused = focused,
+                InputEvent::Mouse {
+                    x,
+                    y,
+                    left,
+                    right,
+                    middle,
+                    wheel,
+                } => {
+                    let mouse = &mut console.mouse;
+                    mouse.x = x;
+                    mouse.y = y;
+                    mouse.held = [left, right, middle];
+                    mouse.wheel += wheel;
+                }
+            }
+        }
+        if !console.focused {
+            console.mouse.held = [false; 3];
+        }
+        // The key state is global, so keys pressed for another window would reach the game
+        match console.focused {
+            true => console.keys.update(win32::key_down),
+            false => console.keys.update(|_| false),
+        }
+        if !game.on_update(&mut console, delta_time) {
+            return Ok(());
+        }
+
+        let screen = &console.screen;
+        buffer
+            .write(screen.cells(), screen.colors())
+            .map_err(GameError::Console)?;
+        if let Some(title) = console.title.take() {
+            win32::set_title(&title).map_err(GameError::Console)?;
+        }
+        let mut frame_time = console
+            .max_fps
+            .map_or(Duration::ZERO, |fps| Duration::from_secs_f32(1.0 / fps));
+        if !console.focused {
+            frame_time = frame_time.max(UNFOCUSED_FRAME_TIME);
+        }
+        thread::sleep(frame_time.saturating_sub(start.elapsed()));
+    }
+}
+
+/// Only the Windows console is supported for now.
+#[cfg(not(windows))]
+pub fn run(
+    _game: &mut impl ConsoleGameEngine,
+    _width: usize,
+    _height: usize,
+) -> Result<(), GameError> {
+    Err(GameError::Console(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the console game engine only runs in the Windows console",
+    )))
+}
@@ -0,0 +1,20 @@
+//! The errors the engine reports instead of panicking.
+
+use std::{io, path::PathBuf};
+use thiserror::Error;
+
+/// Anything that can stop a game from starting or running. The underlying error is
+/// available through [`std::error::Error::source`].
+#[derive(Debug, Error)]
+pub enum GameError {
+    /// The console screen buffer couldn't be created or written to.
+    #[error("couldn't use the console")]
+    Console(#[source] io::Error),
+    #[error("couldn't load {}", path.display())]
+    Asset {
+        /// Relative to the asset directories.
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
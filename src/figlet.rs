@@ -0,0 +1,122 @@
+use olc_fps::{assets, Asset, Screen};
+use std::{collections::HashMap, io};
+
+/// A FIGlet font (`.flf`), see <http://www.jave.de/figlet/figfont.html> for the format.
+///
+/// Only full-width layout is supported, glyphs are drawn side by side without smushing.
+pub struct Font {
+    height: usize,
+    glyphs: HashMap<char, Vec<String>>,
+}
+
+impl Font {
+    /// Returns `None` if the header is invalid. Fonts missing some of the required
+    /// characters are accepted, those characters are simply not drawn.
+    pub fn parse(source: &str) -> Option<Font> {
+        let mut lines = source.lines();
+        let header = lines.next()?;
+        if !header.starts_with("flf2a") {
+            return None;
+        }
+        let mut header = header.chars().skip(5);
+        let hardblank = header.next()?;
+        let fields: Vec<i64> = header
+            .collect::<String>()
+            .split_whitespace()
+            .map_while(|field| field.parse().ok())
+            .collect();
+        let (height, comment_lines) = match fields.as_slice() {
+            [height, _baseline, _max_length, _old_layout, comment_lines, ..]
+                if *height > 0 && *comment_lines >= 0 =>
+            {
+                (*height as usize, *comment_lines as usize)
+            }
+            _ => return None,
+        };
+        let mut lines = lines.skip(comment_lines);
+
+        let mut glyphs = HashMap::new();
+        for code in 32..=126u8 {
+            match read_glyph(&mut lines, height, hardblank) {
+                Some(glyph) => glyphs.insert(code as char, glyph),
+                None => break,
+            };
+        }
+        // Code-tagged characters follow the required ones, each preceded by its code
+        while let Some(tag) = lines.next() {
+            let code = tag.split_whitespace().next().and_then(parse_code);
+            let glyph = read_glyph(&mut lines, height, hardblank);
+            match (code.and_then(std::char::from_u32), glyph) {
+                (Some(c), Some(glyph)) => glyphs.insert(c, glyph),
+                _ => break,
+            };
+        }
+
+        // Otherwise nothing bounds the height, and rendering would allocate that many lines
+        Some(Font { height, glyphs }).filter(|font| !font.glyphs.is_empty())
+    }
+
+    /// Rows each rendered line takes.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Draws `text` in big letters centered across the screen, from row `top` down, for
+    /// headings and numbers that need to stand out.
+    pub fn draw_centered(&self, screen: &mut Screen, top: i32, text: &str, color: u16) {
+        for (row, line) in self.render(text).iter().enumerate() {
+            screen.centered_string(top + row as i32, line, color);
+        }
+    }
+
+    /// Renders `text` as `height` lines of the same width. Lowercase letters fall back to
+    /// uppercase when the font doesn't have them.
+    pub fn render(&self, text: &str) -> Vec<String> {
+        let mut lines = vec![String::new(); self.height];
+        for c in text.chars() {
+            let glyph = self
+                .glyphs
+                .get(&c)
+                .or_else(|| self.glyphs.get(&c.to_ascii_uppercase()));
+            if let Some(glyph) = glyph {
+                for (line, row) in lines.iter_mut().zip(glyph) {
+                    line.push_str(row);
+                }
+            }
+        }
+        lines
+    }
+}
+
+impl Asset for Font {
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Font::parse(assets::text(bytes)?)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a FIGlet font"))
+    }
+}
+
+/// Reads the next `height` lines, without their endmarks and with hardblanks turned to spaces.
+fn read_glyph<'a>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    height: usize,
+    hardblank: char,
+) -> Option<Vec<String>> {
+    (0..height)
+        .map(|_| {
+            let line = lines.next()?.trim_end();
+            let endmark = line.chars().last()?;
+            Some(line.trim_end_matches(endmark).replace(hardblank, " "))
+        })
+        .collect()
+}
+
+/// Character codes may be written in decimal, hexadecimal (`0x`) or octal (leading `0`).
+fn parse_code(code: &str) -> Option<u32> {
+    if let Some(hex) = code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16).ok()
+    } else if code.len() > 1 && code.starts_with('0') {
+        u32::from_str_radix(&code[1..], 8).ok()
+    } else {
+        code.parse().ok()
+    }
+}
@@ -0,0 +1,196 @@
+//! Feeds the map, asset and save file parsers broken versions of real files, to make sure
+//! none of them panics or hangs on what it could find on disk. Every case comes from a fixed
+//! seed so a failure can be replayed, and the failing input is printed.
+
+use crate::{
+    controls::{self, Action},
+    figlet::Font,
+    init_cameras, init_objectives, init_signs,
+    leaderboard::{self, Leaderboard},
+    map_report::Report,
+    settings::{Config, Settings},
+    stats::Stats,
+    time_trial::Medals,
+    with_tile_kinds, EMBEDDED_ASSETS,
+};
+use olc_fps::{locale::StringTable, Map};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+/// Broken versions tried for each parser.
+const CASES: usize = 2000;
+
+/// Longer than any parser should take on these small files.
+const TIME_LIMIT: Duration = Duration::from_secs(1);
+
+/// Bits that mean something to one parser or another.
+const TOKENS: [&str; 18] = [
+    "\n",
+    "\r\n",
+    "\0",
+    " ",
+    "=",
+    "@",
+    "#",
+    ",",
+    "-1",
+    "NaN",
+    "inf",
+    "99999999999999999999",
+    "0x",
+    "é",
+    "version 2\n",
+    "version 999\n",
+    "flf2a$ 99999999 1 1 0 0\n",
+    "@par -1\n",
+];
+
+/// A xorshift generator.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % max.max(1) as u64) as usize
+    }
+}
+
+/// `source` with a few random cuts, copies and insertions.
+fn mutate(rng: &mut Rng, source: &str) -> String {
+    let mut chars: Vec<char> = source.chars().collect();
+    for _ in 0..1 + rng.below(4) {
+        let at = rng.below(chars.len() + 1);
+        let len = rng.below(16).min(chars.len() - at);
+        match rng.below(4) {
+            0 => {
+                chars.drain(at..at + len);
+            }
+            1 => {
+                let copy: Vec<char> = chars[at..at + len].to_vec();
+                let to = rng.below(chars.len() + 1);
+                chars.splice(to..to, copy);
+            }
+            2 => {
+                let token = TOKENS[rng.below(TOKENS.len())];
+                chars.splice(at..at, token.chars());
+            }
+            _ => chars.truncate(at),
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Runs `parse` on broken versions of `source`, failing with the input that made it panic
+/// or take too long.
+fn fuzz(seed: u64, source: &str, parse: impl Fn(&str)) {
+    let mut rng = Rng(seed);
+    for case in 0..CASES {
+        let input = mutate(&mut rng, source);
+        let started = Instant::now();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| parse(&input)));
+        assert!(result.is_ok(), "case {} panicked on {:?}", case, input);
+        let took = started.elapsed();
+        assert!(
+            took < TIME_LIMIT,
+            "case {} took {:?} on {:?}",
+            case,
+            took,
+            input
+        );
+    }
+}
+
+fn embedded(path: &str) -> &'static str {
+    let (_, bytes) = EMBEDDED_ASSETS.iter().find(|(p, _)| *p == path).unwrap();
+    std::str::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn maps() {
+    let settings = Settings::load(&Config::default());
+    fuzz(1, embedded("maps/start.map"), |source| {
+        if let Ok(map) = Map::parse(source) {
+            let map = with_tile_kinds(map, &settings);
+            leaderboard::map_hash(&map);
+            Report::of(&map);
+            init_signs(&map);
+            init_cameras(&map);
+            init_objectives(&map);
+        }
+    });
+}
+
+#[test]
+fn string_tables() {
+    fuzz(2, embedded("lang/en.lang"), |source| {
+        let _ = StringTable::parse(source);
+    });
+}
+
+#[test]
+fn fonts() {
+    // A header alone used to make a font as tall as it said, with no glyph to bound it
+    assert!(Font::parse("flf2a$ 99999999 1 1 0 0\n").is_none());
+    fuzz(3, embedded("fonts/block.flf"), |source| {
+        if let Some(font) = Font::parse(source) {
+            font.render("12:34.56");
+        }
+    });
+}
+
+#[test]
+fn leaderboards() {
+    let source = "version 2\n00000000000000ab 1250 ADA\n00000000000000cd 9.5 BOB\n";
+    fuzz(4, source, |source| {
+        let leaderboard = Leaderboard::parse(source);
+        for entry in leaderboard.top(0xab).iter().chain(leaderboard.top(0xcd)) {
+            assert!(entry.time.is_finite() && entry.time >= 0.0);
+        }
+        leaderboard.to_string();
+    });
+}
+
+#[test]
+fn stats() {
+    let source =
+        "version = 1\ngames_started = 3\nlevels_completed = 1\nplay_time = 60.5\ndistance_walked = 12\n";
+    fuzz(5, source, |source| {
+        let stats = Stats::parse(source);
+        assert!(stats.play_time.is_finite() && stats.play_time >= 0.0);
+        assert!(stats.distance_walked.is_finite() && stats.distance_walked >= 0.0);
+    });
+}
+
+#[test]
+fn medals() {
+    fuzz(
+        6,
+        "version 1\n00000000000000ab gold\n00000000000000cd bronze\n",
+        |source| {
+            Medals::parse(source);
+        },
+    );
+}
+
+#[test]
+fn bindings() {
+    fuzz(
+        7,
+        "forward = W\nconfirm = SPACE, ENTER\npalette = F3\n",
+        |source| {
+            let actions = controls::parse(source);
+            // Every action stays usable
+            for action in Action::ALL.iter() {
+                assert!(
+                    actions.keys(*action).next().is_some(),
+                    "{:?} lost its keys",
+                    action
+                );
+            }
+        },
+    );
+}
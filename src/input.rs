@@ -0,0 +1,168 @@
+//! Actions bound to keys, sampled once per tick.
+//!
+//! Games ask what the player wants to do rather than which keys are down. That way keys can
+//! be rebound, and an action bound to several keys reads the same whichever one is used.
+//! Actions also have an analog value from 0 to 1: keys give all or nothing while a gamepad
+//! stick, fed in with [`Actions::set_analog`], can be anywhere in between.
+
+use crate::engine::Keys;
+use std::{collections::HashMap, hash::Hash};
+
+/// How long, in seconds, a press stays buffered unless [`Actions::with_buffer`] says otherwise.
+pub const DEFAULT_BUFFER: f32 = 0.15;
+
+/// How far an analog input has to go for its action to count as held.
+const ANALOG_HELD: f32 = 0.5;
+
+#[derive(Clone, Copy, Default)]
+struct State {
+    held: bool,
+    previous: bool,
+    /// Seconds since the last press that hasn't been taken yet.
+    buffered: Option<f32>,
+    /// From the keys when one is held, from the analog input otherwise.
+    value: f32,
+    analog: f32,
+}
+
+/// The state of every action of type `A`, e.g. an enum of everything the player can do.
+///
+/// ```
+/// use olc_fps::{input::Actions, keys};
+///
+/// #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// enum Action {
+///     Jump,
+/// }
+///
+/// let mut actions = Actions::new()
+///     .with_binding(Action::Jump, keys::SPACE)
+///     .with_binding(Action::Jump, 'W' as i32);
+/// assert!(!actions.take(Action::Jump));
+/// ```
+pub struct Actions<A> {
+    bindings: Vec<(A, i32)>,
+    states: HashMap<A, State>,
+    buffer: f32,
+}
+
+impl<A: Copy + Eq + Hash> Default for Actions<A> {
+    fn default() -> Self {
+        Actions {
+            bindings: Vec::new(),
+            states: HashMap::new(),
+            buffer: DEFAULT_BUFFER,
+        }
+    }
+}
+
+impl<A: Copy + Eq + Hash> Actions<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `key` trigger `action`, on top of the keys already bound to it.
+    pub fn with_binding(mut self, action: A, key: i32) -> Self {
+        self.bind(action, key);
+        self
+    }
+
+    /// Sets how long presses stay around for [`Actions::take`].
+    pub fn with_buffer(mut self, seconds: f32) -> Self {
+        self.buffer = seconds.max(0.0);
+        self
+    }
+
+    pub fn bind(&mut self, action: A, key: i32) {
+        self.bindings.push((action, key));
+        self.states.entry(action).or_default();
+    }
+
+    /// Stops `key` from triggering `action`.
+    pub fn unbind(&mut self, action: A, key: i32) {
+        self.bindings
+            .retain(|(bound, bound_key)| (*bound, *bound_key) != (action, key));
+    }
+
+    /// The keys bound to `action`, in the order they were.
+    pub fn keys(&self, action: A) -> impl Iterator<Item = i32> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(bound, _)| *bound == action)
+            .map(|(_, key)| *key)
+    }
+
+    /// Feeds an analog reading from 0 to 1 for `action`, like how far a stick is pushed its
+    /// way. It's kept until the next reading, so sources should set it before each update.
+    pub fn set_analog(&mut self, action: A, value: f32) {
+        self.states.entry(action).or_default().analog = value.clamp(0.0, 1.0);
+    }
+
+    /// Starts a new tick: an action is held while any of its keys is, or while its analog
+    /// input is pushed at least halfway.
+    pub fn update(&mut self, keys: &Keys, delta_time: f32) {
+        let (bindings, buffer) = (&self.bindings, self.buffer);
+        for (action, state) in self.states.iter_mut() {
+            state.previous = state.held;
+            let key_held = bindings
+                .iter()
+                .any(|(bound, key)| bound == action && keys.held(*key));
+            state.value = if key_held { 1.0 } else { state.analog };
+            state.held = key_held || state.analog >= ANALOG_HELD;
+            state.buffered = if state.held && !state.previous {
+                Some(0.0)
+            } else {
+                state
+                    .buffered
+                    .map(|age| age + delta_time)
+                    .filter(|age| *age <= buffer)
+            };
+        }
+    }
+
+    fn state(&self, action: A) -> State {
+        self.states.get(&action).copied().unwrap_or_default()
+    }
+
+    pub fn held(&self, action: A) -> bool {
+        self.state(action).held
+    }
+
+    /// How much of the action is wanted, from 0 to 1.
+    pub fn value(&self, action: A) -> f32 {
+        self.state(action).value
+    }
+
+    /// Combines two opposite actions into a value from -1 (all `negative`) to 1.
+    pub fn axis(&self, negative: A, positive: A) -> f32 {
+        self.value(positive) - self.value(negative)
+    }
+
+    /// True only on the tick the action starts.
+    pub fn pressed(&self, action: A) -> bool {
+        let state = self.state(action);
+        state.held && !state.previous
+    }
+
+    /// True only on the tick the action stops.
+    pub fn released(&self, action: A) -> bool {
+        let state = self.state(action);
+        !state.held && state.previous
+    }
+
+    /// Forgets buffered presses, e.g. when switching screens so a press doesn't act on both.
+    pub fn clear_buffer(&mut self) {
+        for state in self.states.values_mut() {
+            state.buffered = None;
+        }
+    }
+
+    /// Takes a press from the last few ticks, for single-press actions. Each press is only
+    /// taken once, and one made a moment before the action was possible still counts.
+    pub fn take(&mut self, action: A) -> bool {
+        match self.states.get_mut(&action) {
+            Some(state) => state.buffered.take().is_some(),
+            None => false,
+        }
+    }
+}
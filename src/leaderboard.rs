@@ -0,0 +1,292 @@
+use crate::{menu::TextInput, paths, SCREEN_HEIGHT, SCREEN_WIDTH};
+use olc_fps::{keys, Align, Border, Keys, Locale, Map, Mouse, MouseButton, Palette, Screen};
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// How many times are kept for each map.
+const TOP: usize = 10;
+/// Of the leaderboard file format, see [`Leaderboard::parse`] for what each one changed.
+const VERSION: u32 = 2;
+pub const MAX_NAME_LENGTH: usize = 12;
+
+/// Where best times are kept between runs.
+pub fn path(data_dir: &Path) -> PathBuf {
+    data_dir.join("leaderboard.txt")
+}
+
+/// Identifies a map by its tiles, so edited maps get a leaderboard of their own.
+pub fn map_hash(map: &Map) -> u64 {
+    // FNV-1a, which unlike the std hashers stays the same across Rust versions
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    let mut add = |value: u32| {
+        for byte in value.to_le_bytes().iter() {
+            hash = (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    add(map.width() as u32);
+    add(map.height() as u32);
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            add(map.get(x, y).map_or(0, |tile| tile as u32));
+        }
+    }
+    hash
+}
+
+pub struct Entry {
+    pub name: String,
+    /// Seconds taken to complete the level.
+    pub time: f32,
+}
+
+/// The best completion times of every map, fastest first.
+#[derive(Default)]
+pub struct Leaderboard {
+    maps: HashMap<u64, Vec<Entry>>,
+}
+
+impl Leaderboard {
+    /// A missing or unreadable file is an empty leaderboard, so it never stops the game.
+    pub fn load(path: &Path) -> Leaderboard {
+        Leaderboard::parse(&paths::read(path))
+    }
+
+    /// Reads a `version 2` line followed by a `hash hundredths name` line per entry. Lines
+    /// that can't be read are skipped.
+    ///
+    /// Files from before there was a version line are version 1, which kept times in
+    /// seconds with every digit of an `f32`. They're rounded to the hundredths shown in
+    /// game, so times that look tied are. Files from a newer version are read as the current
+    /// one, as far as that goes.
+    pub fn parse(source: &str) -> Leaderboard {
+        let mut leaderboard = Leaderboard::default();
+        let mut lines = source.lines().peekable();
+        let version = lines
+            .next_if(|line| line.starts_with("version "))
+            .and_then(|line| line["version ".len()..].trim().parse().ok())
+            .unwrap_or(1);
+        for line in lines {
+            let mut fields = line.splitn(3, ' ');
+            let hash = fields.next().and_then(|f| u64::from_str_radix(f, 16).ok());
+            let time = fields.next().and_then(|f| match version {
+                1 => f
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                    .map(|seconds| (seconds * 100.0).round()),
+                _ => f.parse::<u32>().ok().map(|hundredths| hundredths as f32),
+            });
+            if let (Some(hash), Some(time), Some(name)) = (hash, time, fields.next()) {
+                let name = name.to_string();
+                let time = time / 100.0;
+                leaderboard.insert(hash, Entry { name, time });
+            }
+        }
+        leaderboard
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        paths::write(path, &self.to_string())
+    }
+
+    pub fn top(&self, map: u64) -> &[Entry] {
+        self.maps
+            .get(&map)
+            .map_or(&[], |entries| entries.as_slice())
+    }
+
+    /// Where `time` would rank on `map`, or `None` if it's too slow to make the table.
+    pub fn rank(&self, map: u64, time: f32) -> Option<usize> {
+        let rank = self.top(map).iter().filter(|e| e.time <= time).count();
+        Some(rank).filter(|rank| *rank < TOP)
+    }
+
+    pub fn insert(&mut self, map: u64, entry: Entry) {
+        let entries = self.maps.entry(map).or_default();
+        let rank = entries.iter().filter(|e| e.time <= entry.time).count();
+        entries.insert(rank, entry);
+        entries.truncate(TOP);
+    }
+}
+
+/// The contents of a leaderboard file in the current version.
+impl fmt::Display for Leaderboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version {}", VERSION)?;
+        for (hash, entries) in &self.maps {
+            for entry in entries {
+                let hundredths = (entry.time * 100.0).round() as u32;
+                writeln!(f, "{:016x} {} {}", hash, hundredths, entry.name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Shown once a level is complete, with the best times and name entry for a new record.
+pub struct Intermission {
+    map: u64,
+    time: f32,
+    /// Won in a time trial.
+    medal: Option<&'static str>,
+    /// Where the new record goes while its name is being typed.
+    rank: Option<usize>,
+    name: TextInput,
+}
+
+impl Intermission {
+    pub fn new(leaderboard: &Leaderboard, map: u64, time: f32) -> Self {
+        Intermission {
+            map,
+            time,
+            medal: None,
+            rank: leaderboard.rank(map, time),
+            name: TextInput::new(MAX_NAME_LENGTH),
+        }
+    }
+
+    /// Starts the name of a new record out as `name`, to confirm or change.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name.text = name.chars().take(MAX_NAME_LENGTH).collect();
+        self
+    }
+
+    /// Keeps the time off the table unless `recorded`, for runs that bent the rules.
+    pub fn with_recorded(mut self, recorded: bool) -> Self {
+        if !recorded {
+            self.rank = None;
+        }
+        self
+    }
+
+    /// Shows the medal the time won, a key into the `Locale`.
+    pub fn with_medal(mut self, medal: Option<&'static str>) -> Self {
+        self.medal = medal;
+        self
+    }
+
+    /// Types the name of a new record, then waits to be dismissed. Returns true once a
+    /// record was entered, for the leaderboard to be saved.
+    pub fn update(&mut self, keys: &Keys, leaderboard: &mut Leaderboard) -> bool {
+        if self.rank.is_none() {
+            return false;
+        }
+        if self.name.update(keys) {
+            let name = std::mem::take(&mut self.name.text);
+            leaderboard.insert(
+                self.map,
+                Entry {
+                    name,
+                    time: self.time,
+                },
+            );
+            self.rank = None;
+            return true;
+        }
+        false
+    }
+
+    /// True once there's no name left to type and the player wants to move on.
+    pub fn done(&self, keys: &Keys, mouse: &Mouse) -> bool {
+        let next = keys.pressed(keys::SPACE) || keys.pressed(keys::RETURN);
+        self.rank.is_none() && (next || mouse.pressed(MouseButton::Left))
+    }
+
+    /// Fills the screen with the player's time above the table of best times.
+    pub fn draw(
+        &self,
+        screen: &mut Screen,
+        leaderboard: &Leaderboard,
+        locale: &Locale,
+        palette: &Palette,
+    ) {
+        screen.clear(' ', palette.background);
+        let (width, height) = (50, TOP as i32 + 10);
+        let left = (SCREEN_WIDTH as i32 - width) / 2;
+        let top = (SCREEN_HEIGHT as i32 - height) / 2;
+        screen.frame(left, top, width, height, &Border::DOUBLE, palette.frame);
+        screen.centered_string(top + 1, locale.get("leaderboard.title"), palette.highlight);
+        let time = format_time(self.time);
+        let mut your_time = locale.format("leaderboard.your_time", &[("time", &time)]);
+        if let Some(medal) = self.medal {
+            your_time = format!("{} - {}", your_time, locale.get(medal));
+        }
+        screen.centered_string(top + 3, &your_time, palette.text);
+
+        let mut rows: Vec<(String, f32, bool)> = leaderboard
+            .top(self.map)
+            .iter()
+            .map(|e| (e.name.clone(), e.time, false))
+            .collect();
+        if let Some(rank) = self.rank {
+            rows.insert(rank, (format!("{}_", self.name.text), self.time, true));
+            rows.truncate(TOP);
+        }
+        for (i, (name, time, typing)) in rows.iter().enumerate() {
+            let y = top + 5 + i as i32;
+            let color = if *typing {
+                palette.highlight
+            } else {
+                palette.text
+            };
+            let name = format!("{:>2}. {}", i + 1, name);
+            screen.string(left + 3, y, &name, color);
+            let time = format_time(*time);
+            screen.aligned_string(left + 3, y, width - 6, &time, Align::Right, color);
+        }
+
+        let prompt = match self.rank {
+            Some(_) => "leaderboard.new_record",
+            None => "leaderboard.continue",
+        };
+        screen.centered_string(top + height - 2, locale.get(prompt), palette.text);
+    }
+}
+
+/// Minutes, seconds and hundredths, e.g. `1:05.42`.
+pub fn format_time(seconds: f32) -> String {
+    let hundredths = (seconds * 100.0).round() as u32;
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(leaderboard: &Leaderboard, map: u64) -> Vec<(&str, f32)> {
+        let top = leaderboard.top(map).iter();
+        top.map(|e| (e.name.as_str(), e.time)).collect()
+    }
+
+    #[test]
+    fn migrates_seconds_from_version_1() {
+        let source = "00000000000000ab 42.123456 ADA\n00000000000000ab 12.5 BOB\n";
+        let leaderboard = Leaderboard::parse(source);
+        assert_eq!(times(&leaderboard, 0xab), [("BOB", 12.5), ("ADA", 42.12)]);
+    }
+
+    #[test]
+    fn reads_back_what_it_saves() {
+        let mut leaderboard = Leaderboard::default();
+        let name = "ADA".to_string();
+        leaderboard.insert(0xab, Entry { name, time: 61.004 });
+        let saved = leaderboard.to_string();
+        assert_eq!(saved, "version 2\n00000000000000ab 6100 ADA\n");
+        assert_eq!(times(&Leaderboard::parse(&saved), 0xab), [("ADA", 61.0)]);
+    }
+
+    #[test]
+    fn skips_broken_lines() {
+        let source = "version 2\n00000000000000ab 1250 ADA\nnot a time\n00000000000000ab 9.5 BOB\n";
+        let leaderboard = Leaderboard::parse(source);
+        assert_eq!(times(&leaderboard, 0xab), [("ADA", 12.5)]);
+    }
+}
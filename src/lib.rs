@@ -0,0 +1,29 @@
+//! A tiny engine for games drawn with characters in the console, and the home of the
+//! command line FPS that comes with it.
+
+pub mod assets;
+pub mod collision;
+pub mod draw;
+mod engine;
+pub mod error;
+pub mod input;
+pub mod locale;
+pub mod map;
+pub mod math;
+pub mod palette;
+pub mod raycast;
+#[cfg(test)]
+mod testing;
+pub mod time;
+#[cfg(windows)]
+mod win32;
+
+pub use assets::{Asset, Assets, Handle};
+pub use draw::{color, Align, Border, Screen};
+pub use engine::{keys, run, Console, ConsoleGameEngine, Keys, Mouse, MouseButton};
+pub use error::GameError;
+pub use locale::Locale;
+pub use map::{Map, Segment};
+pub use palette::Palette;
+pub use raycast::{raycast, RayHit, Side};
+pub use time::{Time, Timer};
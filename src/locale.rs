@@ -0,0 +1,83 @@
+//! Translated strings, looked up by key with a fallback chain.
+
+use crate::assets::{self, Asset};
+use std::{collections::HashMap, fmt::Display, io};
+
+/// The strings of one language, read from lines of `key = value`.
+///
+/// Blank lines and lines starting with `#` are ignored. Values can refer to arguments
+/// with `{name}`, see [`Locale::format`].
+#[derive(Clone, Default)]
+pub struct StringTable {
+    strings: HashMap<String, String>,
+}
+
+impl StringTable {
+    /// Returns the line number of the first line that isn't a `key = value` pair on error.
+    pub fn parse(source: &str) -> Result<StringTable, usize> {
+        let mut strings = HashMap::new();
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or(number + 1)?;
+            strings.insert(key.trim().to_string(), value.trim().to_string());
+        }
+        Ok(StringTable { strings })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+}
+
+impl Asset for StringTable {
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        StringTable::parse(assets::text(bytes)?).map_err(|line| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {} isn't a `key = value` pair", line),
+            )
+        })
+    }
+}
+
+/// Looks strings up in each table in turn, usually the chosen language then English.
+#[derive(Clone, Default)]
+pub struct Locale {
+    tables: Vec<StringTable>,
+}
+
+impl Locale {
+    /// `tables` go from the most to the least preferred.
+    pub fn new(tables: Vec<StringTable>) -> Self {
+        Locale { tables }
+    }
+
+    /// The string for `key`, or the key itself when no table has it so missing
+    /// translations stand out without breaking anything.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.tables
+            .iter()
+            .find_map(|table| table.get(key))
+            .unwrap_or(key)
+    }
+
+    /// The string for `key` with each `{name}` replaced by the matching argument.
+    ///
+    /// ```
+    /// use olc_fps::locale::{Locale, StringTable};
+    ///
+    /// let en = StringTable::parse("kills = {kills}/{total} kills").unwrap();
+    /// let locale = Locale::new(vec![en]);
+    /// assert_eq!(locale.format("kills", &[("kills", &3), ("total", &10)]), "3/10 kills");
+    /// ```
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+}
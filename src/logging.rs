@@ -0,0 +1,27 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tracing::level_filters::LevelFilter;
+
+/// Where the log goes, it can't share the console with the game's screen buffer.
+pub fn path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("olc_fps.log")
+}
+
+/// Starts writing events up to `level` to a fresh log file in `cache_dir`. Nothing is
+/// written, and no file is created, when `level` is off.
+pub fn init(level: LevelFilter, cache_dir: &Path) -> io::Result<()> {
+    if level == LevelFilter::OFF {
+        return Ok(());
+    }
+    let file = File::create(path(cache_dir))?;
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .init();
+    Ok(())
+}
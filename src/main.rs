@@ -1,261 +1,1960 @@
-#![feature(slice_fill)]
-
-use std::{cmp::Ordering, ptr, time::Instant};
-use winapi::{
-    shared::ntdef::NULL,
-    um::{
-        wincon::{
-            CreateConsoleScreenBuffer, SetConsoleActiveScreenBuffer, WriteConsoleOutputCharacterW,
-            CONSOLE_TEXTMODE_BUFFER,
-        },
-        wincontypes::COORD,
-        winnt::{GENERIC_READ, GENERIC_WRITE, HANDLE},
-        winuser::GetAsyncKeyState,
-    },
+mod controls;
+mod cutscene;
+mod daylight;
+mod dialogue;
+mod figlet;
+#[cfg(test)]
+mod fuzz;
+mod leaderboard;
+mod logging;
+mod map_report;
+mod menu;
+mod movement;
+mod objectives;
+mod paths;
+mod powerups;
+mod recording;
+mod screenshot;
+mod settings;
+mod stats;
+mod time_trial;
+mod title;
+mod weather;
+
+use controls::{Action, Conflict};
+use cutscene::{Caption, Cutscene, CutscenePlayer, Keyframe};
+use daylight::Daylight;
+use dialogue::{Dialogue, Sign, SIGN};
+use figlet::Font;
+use leaderboard::{Intermission, Leaderboard};
+use menu::{Item, Menu, MenuAction, TextInput, Widget};
+use movement::{Stamina, Surface};
+use objectives::{Event, Goal, Objective, Objectives, EXIT};
+use olc_fps::{
+    collision::{self, Body},
+    input::Actions,
+    keys,
+    locale::StringTable,
+    math::{approach, direction, wrap_angle, Vec2},
+    raycast::{ray_hits, raycast},
+    Align, Assets, Border, Console, ConsoleGameEngine, GameError, Handle, Keys, Locale, Map,
+    MouseButton, Palette, Screen, Segment, Side, Time, Timer,
+};
+use powerups::{Effects, Powerup};
+use recording::Recording;
+use settings::Settings;
+use stats::Stats;
+use std::{
+    cmp::Ordering,
+    collections::hash_map::RandomState,
+    env,
+    error::Error,
+    f32::consts::TAU,
+    fs,
+    hash::{BuildHasher, Hasher},
+    panic,
+    path::PathBuf,
+    process,
 };
+use time_trial::{Medal, Medals, TimeTrial};
+use title::TitleScreen;
+use tracing::{debug, error, info, trace_span, warn};
+use weather::Weather;
 
 const SCREEN_WIDTH: usize = 120;
 const SCREEN_HEIGHT: usize = 40;
 const SCREEN_SIZE: usize = SCREEN_WIDTH * SCREEN_HEIGHT;
 
-const MAP_HEIGHT: usize = 16;
-const MAP_WIDTH: usize = 16;
+/// Where the player starts the level.
+const START: Vec2 = Vec2::new(8.0, 8.0);
+
+/// Iron bars, and a window pane with a frame, that can be seen through but not walked through.
+const BARS: char = '|';
+const WINDOW: char = 'G';
+/// Reflects the room, drawn inside a thin frame.
+const MIRROR: char = 'M';
+/// Thin walls across the middle of their tile and diagonal walls from corner to corner.
+const SEGMENTS: [(char, Segment); 4] = [
+    ('-', Segment::new(Vec2::new(0.0, 0.5), Vec2::new(1.0, 0.5))),
+    ('!', Segment::new(Vec2::new(0.5, 0.0), Vec2::new(0.5, 1.0))),
+    ('/', Segment::new(Vec2::new(0.0, 1.0), Vec2::new(1.0, 0.0))),
+    ('\\', Segment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0))),
+];
+/// Each of these is on the map twice, walking into one comes out of the other.
+const PORTALS: [char; 3] = ['1', '2', '3'];
+/// Tiles that block movement and rays.
+const SOLID_TILES: [char; 9] = ['#', SIGN, BARS, WINDOW, MIRROR, '-', '!', '/', '\\'];
+/// Solid tiles that rays go through, drawing them over what's behind.
+const SEE_THROUGH_TILES: [char; 2] = [BARS, WINDOW];
+
+/// Built into the executable so the game runs from anywhere, files in `assets` override them.
+const EMBEDDED_ASSETS: [(&str, &[u8]); 4] = [
+    (
+        "fonts/block.flf",
+        include_bytes!("../assets/fonts/block.flf"),
+    ),
+    ("lang/en.lang", include_bytes!("../assets/lang/en.lang")),
+    ("lang/fr.lang", include_bytes!("../assets/lang/fr.lang")),
+    ("maps/start.map", include_bytes!("../assets/maps/start.map")),
+];
+
+/// Lines of the title prompt that can be picked: start, statistics and options.
+const TITLE_CHOICES: usize = 3;
+
+/// How often, in seconds of play, lifetime stats are saved.
+const STATS_SAVE_INTERVAL: f32 = 30.0;
+
+/// How often, in seconds, asset files are checked for changes.
+const ASSET_POLL_INTERVAL: f32 = 0.5;
 
 const FOV: f32 = std::f32::consts::PI / 4.0;
 
 const DEPTH: f32 = 16.0;
 
+const PLAYER_BODY: Body = Body {
+    radius: 0.2,
+    step_height: 0.25,
+};
+
+/// How often, in seconds, the status in the window title is refreshed.
+const TITLE_STATUS_INTERVAL: f32 = 1.0;
+
+/// Frame rate cap for menus and while paused, where nothing needs to move smoothly.
+const IDLE_FPS: f32 = 30.0;
+
+/// Per second, how fast the movement and turning axes catch up with what's held, and how
+/// fast they settle back once it's let go.
+const AXIS_ACCELERATION: f32 = 8.0;
+const AXIS_DECELERATION: f32 = 16.0;
+
+/// Seconds of game time simulated by each frame step while paused.
+const FRAME_STEP: f32 = 1.0 / 60.0;
+
+/// How long, in seconds, a notice stays on the bottom row.
+const NOTICE_DURATION: f32 = 3.0;
+
+/// What the weather option can be set to: the weather, its value in the config file and its
+/// key into the `Locale`.
+const WEATHER_CHOICES: [(Option<weather::Kind>, &str, &str); 3] = [
+    (Some(weather::Kind::Rain), "rain", "weather.rain"),
+    (Some(weather::Kind::Snow), "snow", "weather.snow"),
+    (None, "none", "weather.none"),
+];
+
+/// Typed cheat codes and the debug command each one runs.
+const CHEAT_CODES: [(&str, DebugCommand); 4] = [
+    ("IDCLIP", DebugCommand::Noclip),
+    ("IDSPISPOPD", DebugCommand::Noclip),
+    ("IDCAMERA", DebugCommand::Spectator),
+    ("RSPAWN", DebugCommand::RandomStart),
+];
+
+#[derive(Clone, Copy)]
 struct Player {
-    x: f32,
-    y: f32,
-    a: f32,
+    position: Vec2,
+    angle: f32,
+    /// Seconds a turn key has been held for, to ramp the turn speed up.
+    turning: f32,
+    /// Tiles per second.
+    velocity: Vec2,
+    /// Eased turning axis, from -1 to 1.
+    turn: f32,
+    /// From 0 standing to 1 crouched.
+    crouch: f32,
+    stamina: Stamina,
+    /// Seconds spent in water since last stepping out, for the view to bob.
+    wading: Option<f32>,
+    /// From 0 to 1, used up under deep water.
+    air: f32,
+    /// Where the player last stood out of the water, to climb back out when out of air.
+    last_dry: Vec2,
+    effects: Effects,
 }
 
-#[cfg(windows)]
-fn main() {
-    let mut player = Player {
-        x: 8.0,
-        y: 8.0,
-        a: 0.0,
+impl Player {
+    /// Puts the player down at `position` facing `angle`, standing still.
+    fn place(&mut self, position: Vec2, angle: f32) {
+        self.position = position;
+        self.angle = angle;
+        self.velocity = Vec2::ZERO;
+        self.last_dry = position;
+    }
+
+    /// The camera looking out of the player's eyes, bobbing while wading unless motion is
+    /// reduced.
+    fn camera(&self, settings: &Settings) -> Camera {
+        let bob = match settings.reduced_motion {
+            true => 0.0,
+            false => self.wading.map_or(0.0, movement::bob),
+        };
+        Camera {
+            position: self.position,
+            angle: self.angle,
+            fov: settings.fov,
+            pitch: movement::CROUCH_PITCH * self.crouch + bob,
+        }
+    }
+}
+
+/// Everything the renderer needs to know about where a view is looked from.
+#[derive(Clone, Copy)]
+struct Camera {
+    position: Vec2,
+    angle: f32,
+    fov: f32,
+    /// Shifts the horizon up (negative) or down (positive), as a fraction of the view height.
+    pitch: f32,
+}
+
+/// Everything besides the map and the camera that changes how views look.
+#[derive(Clone, Copy)]
+struct Ambience {
+    /// Seconds of game time, for animated tiles.
+    time: f32,
+    /// How many shades darker than in broad daylight walls and floors are.
+    darkness: u32,
+    /// Water ripples, it stays still with reduced motion.
+    waves: bool,
+}
+
+/// A rectangle of the screen that a view gets rendered into.
+struct Viewport {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Viewport {
+    const FULL_SCREEN: Viewport = Viewport {
+        left: 0,
+        top: 0,
+        width: SCREEN_WIDTH,
+        height: SCREEN_HEIGHT,
     };
 
-    let mut screen: Vec<u16> = init_screen();
-    let h_console = create_console_buffer();
-    let mut bytes_written: u32 = 0;
+    /// Bottom right corner, where security camera feeds show up.
+    const MONITOR: Viewport = Viewport {
+        left: SCREEN_WIDTH - 42,
+        top: SCREEN_HEIGHT - 13,
+        width: 40,
+        height: 12,
+    };
 
-    let map = init_map();
+    /// Top right corner, leaving the first row for the stats line.
+    const REAR_VIEW: Viewport = Viewport {
+        left: SCREEN_WIDTH - 32,
+        top: 2,
+        width: 30,
+        height: 10,
+    };
+}
 
-    let mut start;
-    let mut end = Instant::now();
+/// A fixed camera placed in the level that can be watched remotely.
+struct SecurityCamera {
+    /// Key into the `Locale`, shown on the monitor frame.
+    name: String,
+    camera: Camera,
+}
 
-    // Game loop
-    loop {
-        start = Instant::now();
-        let delta_time = start - end;
-        end = start;
-        let delta_time = delta_time.as_secs_f32();
+/// Debug toggles used to inspect levels without being blocked by them.
+#[derive(Default)]
+struct Debug {
+    /// Disables wall collision for whoever is being controlled.
+    noclip: bool,
+    /// Free camera detached from the player, rendered instead of the player's view.
+    spectator: Option<Player>,
+}
 
-        handle_controls(&mut player, delta_time, &map);
-        update_screen(&mut screen, &player, &map);
+#[derive(Clone, Copy)]
+enum DebugCommand {
+    Noclip,
+    Spectator,
+    /// Moves the player to a random spot, see [`random_start`].
+    RandomStart,
+}
 
-        let stats = format!(
-            "X={}, Y={}, A={}, FPS={}",
-            player.x,
-            player.y,
-            player.a,
-            1.0 / delta_time
-        );
+impl Debug {
+    fn run(&mut self, command: DebugCommand, player: &mut Player, map: &Map) {
+        match command {
+            DebugCommand::Noclip => self.noclip = !self.noclip,
+            DebugCommand::Spectator => {
+                self.spectator = match self.spectator {
+                    Some(_) => None,
+                    None => Some(*player),
+                }
+            }
+            DebugCommand::RandomStart => {
+                if let Some((position, angle)) = random_start(map) {
+                    player.place(position, angle);
+                }
+            }
+        }
+    }
+}
+
+/// Watches the letters typed during gameplay for one of the `CHEAT_CODES`.
+struct Cheats {
+    enabled: bool,
+    typed: String,
+}
 
-        for (i, c) in stats.chars().enumerate() {
-            screen[i] = c as u16;
+impl Cheats {
+    fn new(enabled: bool) -> Self {
+        Cheats {
+            enabled,
+            typed: String::new(),
         }
+    }
 
-        draw_map(&mut screen, &player, &map);
-        draw_screen_to_console(h_console, &mut screen, &mut bytes_written);
+    fn update(&mut self, keys: &Keys) -> Option<DebugCommand> {
+        if !self.enabled {
+            return None;
+        }
+        for letter in b'A'..=b'Z' {
+            if keys.pressed(letter as i32) {
+                self.typed.push(letter as char);
+            }
+        }
+        let longest = CHEAT_CODES.iter().map(|(code, _)| code.len()).max()?;
+        if self.typed.len() > longest {
+            self.typed.drain(..self.typed.len() - longest);
+        }
+        let (_, command) = CHEAT_CODES
+            .iter()
+            .find(|(code, _)| self.typed.ends_with(code))?;
+        self.typed.clear();
+        Some(*command)
     }
 }
 
-fn create_console_buffer() -> HANDLE {
-    let h_console;
-    unsafe {
-        h_console = CreateConsoleScreenBuffer(
-            GENERIC_READ | GENERIC_WRITE,
-            0,
-            ptr::null(),
-            CONSOLE_TEXTMODE_BUFFER,
-            NULL,
+enum State {
+    Title(TitleScreen),
+    /// Keeps the title screen to go back to.
+    Stats(TitleScreen),
+    /// Keeps the title screen to go back to.
+    Options(TitleScreen, Menu),
+    /// Keeps the title screen and the options menu to go back to.
+    Controls(TitleScreen, Menu, Menu),
+    Playing,
+    Intermission(Intermission),
+}
+
+struct Game {
+    state: State,
+    player: Player,
+    assets: Assets,
+    /// Big letters for the title and the HUD.
+    font: Handle<Font>,
+    /// Reloaded whenever its file changes, `map` is kept in sync with it.
+    map_handle: Handle<Map>,
+    map: Map,
+    /// Of the map the run began on, before anything on it got picked up, to keep times and
+    /// medals apart from those of other versions of it.
+    map_hash: u64,
+    asset_poll: Timer,
+    signs: Vec<Sign>,
+    dialogue: Option<Dialogue>,
+    objectives: Objectives,
+    debug: Debug,
+    cheats: Cheats,
+    /// Cleared once noclip, the spectator camera or a cheat code gets used, or the map
+    /// changes, so the run doesn't go on the leaderboard or earn a medal.
+    recordable: bool,
+    rear_view: bool,
+    weather: Option<Weather>,
+    daylight: Daylight,
+    cameras: Vec<SecurityCamera>,
+    watched_camera: Option<usize>,
+    cutscene: Option<CutscenePlayer>,
+    ending_played: bool,
+    /// Level time waiting for the ending cutscene to finish before the intermission.
+    finish_time: Option<f32>,
+    leaderboard: Leaderboard,
+    /// Running when playing against the clock.
+    time_trial: Option<TimeTrial>,
+    medals: Medals,
+    /// Won by the time trial that just finished, for the intermission.
+    medal: Option<Medal>,
+    notice: Option<(String, Timer)>,
+    recording: Option<Recording>,
+    time: Time,
+    locale: Locale,
+    /// The language files `locale` is made of, to rebuild it when one of them changes.
+    lang: Vec<Handle<StringTable>>,
+    settings: Settings,
+    stats: Stats,
+    stats_save: Timer,
+    /// The game got paused because the console lost the focus, and resumes when it's back.
+    paused_by_focus: bool,
+    /// When to refresh the window title, if it shows the status.
+    title_status: Option<Timer>,
+    actions: Actions<Action>,
+    menu_actions: Actions<MenuAction>,
+}
+
+impl Game {
+    fn new() -> Result<Self, GameError> {
+        let settings = Settings::load(&settings::Config::load());
+        // Failing to create them shows up as failing to save in them, with the reason
+        for dir in [&settings.data_dir, &settings.cache_dir].iter() {
+            fs::create_dir_all(dir).ok();
+        }
+        if let Err(e) = logging::init(settings.log_level, &settings.cache_dir) {
+            eprintln!(
+                "Logging disabled, {} couldn't be created: {}",
+                logging::path(&settings.cache_dir).display(),
+                e
+            );
+        }
+        let mut assets = Assets::new();
+        for dir in asset_dirs() {
+            assets.add_dir(dir);
+        }
+        for (path, bytes) in EMBEDDED_ASSETS.iter() {
+            assets.embed(*path, bytes);
+        }
+        let font = assets.load::<Font>("fonts/block.flf")?;
+        let map_handle = assets.load::<Map>("maps/start.map")?;
+        let lang = load_lang(&mut assets, &settings.language)?;
+        let locale = locale_of(&assets, &lang);
+        let actions = controls::load(&controls::path());
+        let prompt = title_prompt(&locale, &actions);
+        let map = with_tile_kinds(assets.get(map_handle).clone(), &settings);
+        let daylight = Daylight::new(settings.day_length).with_locked(time_of_day(&map));
+        let game = Game {
+            state: State::Title(
+                TitleScreen::new(assets.get(font), &["Command Line", "FPS"], prompt)
+                    .with_reduced_motion(settings.reduced_motion),
+            ),
+            locale,
+            lang,
+            title_status: Some(Timer::repeating(TITLE_STATUS_INTERVAL))
+                .filter(|_| settings.title_status),
+            stats: Stats::load(&stats::path(&settings.data_dir)),
+            stats_save: Timer::repeating(STATS_SAVE_INTERVAL),
+            paused_by_focus: false,
+            actions,
+            menu_actions: menu::default_actions(),
+            player: starting_player(&map, &settings),
+            map_hash: leaderboard::map_hash(&map),
+            signs: init_signs(&map),
+            cameras: init_cameras(&map),
+            objectives: Objectives::new(init_objectives(&map)),
+            map,
+            map_handle,
+            font,
+            assets,
+            asset_poll: Timer::repeating(ASSET_POLL_INTERVAL),
+            dialogue: None,
+            debug: Debug::default(),
+            cheats: Cheats::new(settings.cheats),
+            recordable: true,
+            rear_view: false,
+            weather: settings.weather.map(Weather::new),
+            daylight,
+            watched_camera: None,
+            cutscene: None,
+            ending_played: false,
+            finish_time: None,
+            leaderboard: Leaderboard::load(&leaderboard::path(&settings.data_dir)),
+            time_trial: Some(TimeTrial::default()).filter(|_| settings.time_trial),
+            medals: Medals::load(&time_trial::path(&settings.data_dir)),
+            medal: None,
+            notice: None,
+            recording: None,
+            time: Time::new(),
+            // Last, since the fields above read from it
+            settings,
+        };
+        Ok(game)
+    }
+
+    /// Puts the player back at the start of a fresh copy of the level, with its objectives
+    /// and time trial started over, for a new run.
+    fn restart(&mut self) {
+        let map = self.assets.get(self.map_handle).clone();
+        self.map = with_tile_kinds(map, &self.settings);
+        self.player = starting_player(&self.map, &self.settings);
+        self.objectives = Objectives::new(init_objectives(&self.map));
+        self.time_trial = Some(TimeTrial::default()).filter(|_| self.settings.time_trial);
+        self.dialogue = None;
+        self.ending_played = false;
+        self.medal = None;
+        // The new run is on the map as it is now, and counts unless it starts out noclipping
+        self.map_hash = leaderboard::map_hash(&self.map);
+        self.recordable = !self.debug.noclip && self.debug.spectator.is_none();
+    }
+
+    /// Picks up the changes made to asset files while the game is running.
+    fn reload_assets(&mut self) {
+        if self.asset_poll.tick(self.time.real_delta()) == 0 {
+            return;
+        }
+        for reload in self.assets.reload_changed() {
+            match reload.result {
+                Ok(()) if reload.id == self.map_handle.id() => {
+                    let map = self.assets.get(self.map_handle).clone();
+                    self.map = with_tile_kinds(map, &self.settings);
+                    // Both point into the lists they came from
+                    self.dialogue = None;
+                    self.watched_camera = None;
+                    self.signs = init_signs(&self.map);
+                    self.cameras = init_cameras(&self.map);
+                    self.objectives.replace(init_objectives(&self.map));
+                    let daylight = Daylight::new(self.settings.day_length);
+                    self.daylight = daylight.with_locked(time_of_day(&self.map));
+                    let path = reload.path.display();
+                    let hash = leaderboard::map_hash(&self.map);
+                    info!(path = %path, hash = %format_args!("{:016x}", hash), "map reloaded");
+                    let notice = match hash == self.map_hash {
+                        true => "notice.reloaded",
+                        // A run played on two maps doesn't count for either of them
+                        false => {
+                            self.recordable = false;
+                            "notice.map_changed"
+                        }
+                    };
+                    self.notify(self.locale.format(notice, &[("path", &path)]));
+                }
+                Ok(()) if self.lang.iter().any(|table| table.id() == reload.id) => {
+                    self.locale = locale_of(&self.assets, &self.lang);
+                    info!(path = %reload.path.display(), "language reloaded");
+                }
+                Ok(()) => info!(path = %reload.path.display(), "asset reloaded"),
+                Err(e) => {
+                    warn!(path = %reload.path.display(), error = %e, "reloading an asset failed");
+                    self.notify(self.locale.format("notice.reload_failed", &[("error", &e)]));
+                }
+            }
+        }
+    }
+
+    fn save_stats(&mut self) {
+        if let Err(e) = self.stats.save(&stats::path(&self.settings.data_dir)) {
+            warn!(error = %e, "saving stats failed");
+            self.notify(self.locale.format("notice.stats_failed", &[("error", &e)]));
+        }
+    }
+
+    /// The map, what's going on and the frame rate, for the window title.
+    fn status(&self) -> String {
+        let mode = match self.state {
+            State::Title(_) => "title",
+            State::Stats(_) => "statistics",
+            State::Options(..) => "options",
+            State::Controls(..) => "controls",
+            State::Intermission(_) => "intermission",
+            State::Playing if self.time.paused() => "paused",
+            State::Playing if self.debug.spectator.is_some() => "spectator",
+            State::Playing if self.debug.noclip => "noclip",
+            State::Playing => "playing",
+        };
+        let map = self.assets.path(self.map_handle.id());
+        let map = map.file_stem().unwrap_or_default().to_string_lossy();
+        let fps = 1.0 / self.time.smoothed_delta();
+        format!("olc_fps - {} - {} - {:.0} FPS", map, mode, fps)
+    }
+
+    /// Applies an item of the options menu for the rest of the game, and saves it to the
+    /// config file.
+    fn apply_option(&mut self, item: &Item) {
+        let settings = &mut self.settings;
+        let value = match (item.id, &item.widget) {
+            ("reduced_motion", Widget::Toggle(on)) => {
+                settings.reduced_motion = *on;
+                (*on as u8).to_string()
+            }
+            ("fov", Widget::Slider { value, .. }) => {
+                settings.fov = value.to_radians();
+                value.to_string()
+            }
+            ("turn_speed", Widget::Slider { value, .. }) => {
+                settings.turn_speed = value.to_radians();
+                value.to_string()
+            }
+            ("weather", Widget::Choice { selected, .. }) => {
+                let (kind, value, _) = WEATHER_CHOICES[*selected];
+                settings.weather = kind;
+                self.weather = kind.map(Weather::new);
+                value.to_string()
+            }
+            ("palette", Widget::Choice { selected, .. }) => {
+                settings.palette = Palette::ALL[*selected];
+                settings.palette.id().to_string()
+            }
+            ("name", Widget::Text { input, .. }) => {
+                settings.name = input.text.clone();
+                input.text.clone()
+            }
+            _ => return,
+        };
+        self.save_option(item.id, &value);
+    }
+
+    /// Switches to the palette after the current one and keeps it for the next launch.
+    fn next_palette(&mut self) {
+        let next = Palette::ALL
+            .iter()
+            .position(|p| std::ptr::eq(*p, self.settings.palette))
+            .map_or(0, |i| (i + 1) % Palette::ALL.len());
+        self.settings.palette = Palette::ALL[next];
+        self.save_option("palette", self.settings.palette.id());
+    }
+
+    /// Writes a setting changed in game to the config file, for the next launch.
+    fn save_option(&mut self, key: &str, value: &str) {
+        if let Err(e) = settings::write_option(key, value) {
+            warn!(error = %e, "saving settings failed");
+            self.notify(
+                self.locale
+                    .format("notice.settings_failed", &[("error", &e)]),
+            );
+        }
+    }
+
+    /// Binds the key just captured by the item `index` of the controls menu to its action,
+    /// and saves the bindings. The menu tells what happened to an action that had the key.
+    fn rebind(&mut self, menu: &mut Menu, index: usize) {
+        let action = Action::ALL[index];
+        let key = match &menu.items()[index].widget {
+            Widget::KeyCapture { keys, .. } => keys[0],
+            _ => return,
+        };
+        let locale = &self.locale;
+        let key_name = controls::key_name(key);
+        let note = if controls::RESERVED.contains(&key) {
+            Some(locale.format("controls.reserved", &[("key", &key_name)]))
+        } else {
+            match controls::rebind(&mut self.actions, action, key) {
+                Some(Conflict::Taken { from }) => Some(locale.format(
+                    "controls.taken",
+                    &[("key", &key_name), ("action", &locale.get(from.name()))],
+                )),
+                Some(Conflict::Swapped { with, key }) => Some(locale.format(
+                    "controls.swapped",
+                    &[
+                        ("action", &locale.get(with.name())),
+                        ("key", &controls::key_name(key)),
+                    ],
+                )),
+                None => None,
+            }
+        };
+        let note = match controls::save(&controls::path(), &self.actions) {
+            Ok(()) => note,
+            Err(e) => {
+                warn!(error = %e, "saving the controls failed");
+                Some(locale.format("controls.save_failed", &[("error", &e)]))
+            }
+        };
+        menu.set_note(note);
+        // Shows what's actually bound, which a reserved key or a conflict changed
+        for (item, action) in menu.items_mut().iter_mut().zip(Action::ALL.iter()) {
+            if let Widget::KeyCapture { keys, .. } = &mut item.widget {
+                *keys = self.actions.keys(*action).collect();
+            }
+        }
+    }
+
+    /// How views look right now, with the time of day and night vision.
+    fn ambience(&self) -> Ambience {
+        let night_vision = self.player.effects.active(Powerup::NightVision);
+        Ambience {
+            time: self.time.total(),
+            darkness: if night_vision {
+                0
+            } else {
+                self.daylight.darkness()
+            },
+            waves: !self.settings.reduced_motion,
+        }
+    }
+
+    /// Shows `text` on the bottom row for a little while.
+    fn notify(&mut self, text: String) {
+        self.notice = Some((text, Timer::once(NOTICE_DURATION)));
+    }
+
+    /// Returns the state to carry on with next frame.
+    fn play(&mut self, console: &mut Console) -> State {
+        let keys = console.keys();
+        let update = trace_span!("update").entered();
+        if keys.pressed(keys::F5) {
+            self.time.set_paused(!self.time.paused());
+        }
+        let stepping = keys.pressed(keys::F6) && self.time.paused();
+        if stepping {
+            self.time.step(FRAME_STEP);
+        }
+        // While paused, input only counts on the ticks stepped through one at a time
+        let running = !self.time.paused() || stepping;
+        let delta_time = self.time.delta();
+
+        // Cutscenes take over the whole screen until they end or get skipped.
+        if let Some(playing) = self.cutscene.as_mut() {
+            let skipped = running && self.actions.take(Action::Confirm);
+            if skipped || !playing.update(delta_time) {
+                self.cutscene = None;
+                if let Some(time) = self.finish_time.take() {
+                    let map = self.map_hash;
+                    let intermission = Intermission::new(&self.leaderboard, map, time)
+                        .with_name(&self.settings.name)
+                        .with_medal(self.medal.map(Medal::name))
+                        .with_recorded(self.recordable);
+                    return State::Intermission(intermission);
+                }
+            }
+        }
+        if let Some(playing) = &self.cutscene {
+            drop(update);
+            let _render = trace_span!("render").entered();
+            let screen = console.screen_mut();
+            let ambience = self.ambience();
+            let camera = Camera {
+                fov: self.settings.fov,
+                ..playing.camera()
+            };
+            update_screen(screen, &camera, &self.map, &ambience, self.settings.palette);
+            playing.draw(screen, &self.locale, self.settings.palette);
+            return State::Playing;
+        }
+
+        let mut out_of_air = false;
+        // An open dialogue pauses the simulation until it's dismissed.
+        let pov = if !running {
+            self.debug.spectator.unwrap_or(self.player)
+        } else if let Some(open) = self.dialogue.take() {
+            self.dialogue = handle_dialogue_keys(keys, &mut self.actions, open, &self.signs);
+            self.debug.spectator.unwrap_or(self.player)
+        } else {
+            // Time trials are played by the rules
+            let debug_key = debug_key(keys).filter(|_| !self.settings.time_trial);
+            for command in debug_key.into_iter().chain(self.cheats.update(keys)) {
+                self.debug.run(command, &mut self.player, &self.map);
+                self.recordable = false;
+            }
+            if self.actions.take(Action::RearView) {
+                self.rear_view = !self.rear_view;
+            }
+            if self.actions.take(Action::Palette) {
+                self.next_palette();
+                let name = self.locale.get(self.settings.palette.name);
+                self.notify(self.locale.format("notice.palette", &[("name", &name)]));
+            }
+            if self.actions.take(Action::Cameras) {
+                self.watched_camera = match self.watched_camera {
+                    None if !self.cameras.is_empty() => Some(0),
+                    Some(i) if i + 1 < self.cameras.len() => Some(i + 1),
+                    _ => None,
+                };
+            }
+            if self.actions.take(Action::Interact) {
+                if let Some((x, y)) = facing_sign(&self.player, &self.map) {
+                    self.dialogue = Dialogue::open(&self.signs, x, y);
+                    self.objectives.handle(Event::SignRead { x, y });
+                }
+            }
+            self.objectives.update(delta_time);
+            let actions = &mut self.actions;
+            match self.debug.spectator.as_mut() {
+                Some(spectator) => {
+                    handle_controls(
+                        spectator,
+                        actions,
+                        delta_time,
+                        &self.map,
+                        true,
+                        &self.settings,
+                    );
+                    *spectator
+                }
+                None => {
+                    let player = &mut self.player;
+                    let noclip = self.debug.noclip;
+                    let before = player.position;
+                    handle_controls(
+                        player,
+                        actions,
+                        delta_time,
+                        &self.map,
+                        noclip,
+                        &self.settings,
+                    );
+                    self.stats.distance_walked += player.position.distance(before);
+                    if passes_over(&self.map, before, player.position, EXIT) {
+                        self.objectives.handle(Event::ExitReached);
+                    }
+                    if let Some(to) = portal_exit(&self.map, before, player.position) {
+                        player.position = to;
+                        player.last_dry = to;
+                    }
+                    if let Some(trial) = self.time_trial.as_mut() {
+                        let (x, y) = (player.position.x as usize, player.position.y as usize);
+                        trial.cross(&self.map, x, y, self.objectives.elapsed());
+                    }
+                    player.effects.update(delta_time);
+                    let (x, y) = (player.position.x as usize, player.position.y as usize);
+                    if let Some(powerup) = self.map.get(x, y).and_then(Powerup::on) {
+                        player.effects.add(powerup);
+                        self.map.set(x, y, '.');
+                    }
+                    if player.air <= 0.0 {
+                        // Climb back out where the player went in, rather than drowning
+                        player.position = player.last_dry;
+                        player.velocity = Vec2::ZERO;
+                        player.air = 1.0;
+                        out_of_air = true;
+                    }
+                    *player
+                }
+            }
+        };
+        if out_of_air {
+            self.notify(self.locale.get("notice.out_of_air").to_string());
+        }
+        if let Some(failed) = self.objectives.failed().map(|o| o.description) {
+            self.restart();
+            let objective = self.locale.get(failed);
+            let text = self
+                .locale
+                .format("notice.objective_failed", &[("objective", &objective)]);
+            self.notify(text);
+        }
+        if !self.ending_played && self.objectives.level_complete() {
+            self.ending_played = true;
+            let cutscene = CutscenePlayer::new(ending_cutscene(&self.player));
+            self.cutscene = Some(cutscene.with_cuts(self.settings.reduced_motion));
+            let time = self.objectives.elapsed();
+            self.finish_time = Some(time);
+            self.stats.levels_completed += 1;
+            self.save_stats();
+            self.medal = time_trial::par(&self.map)
+                .filter(|_| self.time_trial.is_some() && self.recordable)
+                .and_then(|par| Medal::earned(time, par));
+            if let Some(medal) = self.medal {
+                if self.medals.award(self.map_hash, medal) {
+                    if let Err(e) = self.medals.save(&time_trial::path(&self.settings.data_dir)) {
+                        warn!(error = %e, "saving medals failed");
+                        self.notify(self.locale.format("notice.medals_failed", &[("error", &e)]));
+                    }
+                }
+            }
+        }
+        // The play time, the weather and the time of day wait for a dialogue to close too
+        if self.dialogue.is_none() {
+            self.stats.play_time += delta_time;
+            if self.stats_save.tick(delta_time) > 0 {
+                self.save_stats();
+            }
+            if let Some(weather) = self.weather.as_mut() {
+                weather.update(&self.map, pov.position, delta_time);
+            }
+            self.daylight.update(delta_time);
+        }
+        let take_screenshot = keys.pressed(keys::F12);
+        let toggle_recording = keys.pressed(keys::F9);
+        drop(update);
+
+        let _render = trace_span!("render").entered();
+        let screen = console.screen_mut();
+        let camera = pov.camera(&self.settings);
+        let ambience = self.ambience();
+        let palette = self.settings.palette;
+        let depth = update_screen(screen, &camera, &self.map, &ambience, palette);
+        if let Some(weather) = &self.weather {
+            draw_weather(screen, &camera, &depth, weather, palette);
+        }
+        if self.rear_view {
+            let label = self.locale.get("view.rear");
+            draw_rear_view(screen, &camera, &self.map, &ambience, label, palette);
+        }
+        if let Some(watched) = self.watched_camera.map(|i| &self.cameras[i]) {
+            draw_framed_view(
+                screen,
+                &watched.camera,
+                &self.map,
+                &ambience,
+                &Viewport::MONITOR,
+                self.locale.get(&watched.name),
+                palette,
+            );
+        }
+
+        let stats = format!(
+            "X={}, Y={}, A={}, FPS={}, TICK={}{}{}",
+            camera.position.x,
+            camera.position.y,
+            camera.angle,
+            1.0 / self.time.smoothed_delta(),
+            self.time.ticks(),
+            if self.debug.noclip { " NOCLIP" } else { "" },
+            if self.debug.spectator.is_some() {
+                " SPECTATOR"
+            } else {
+                ""
+            },
         );
-        SetConsoleActiveScreenBuffer(h_console);
-    }
-    h_console
-}
-
-fn init_screen() -> Vec<u16> {
-    let mut screen = Vec::with_capacity(SCREEN_SIZE);
-    for _ in 0..=SCREEN_SIZE {
-        screen.push(0);
-    }
-    screen
-}
-
-fn init_map() -> Vec<char> {
-    let mut map = String::new();
-    map.push_str("################");
-    map.push_str("#..............#");
-    map.push_str("#..............#");
-    map.push_str("#..........#...#");
-    map.push_str("#..........#...#");
-    map.push_str("#..............#");
-    map.push_str("#..............#");
-    map.push_str("#..............#");
-    map.push_str("#..............#");
-    map.push_str("#..............#");
-    map.push_str("#..............#");
-    map.push_str("#..............#");
-    map.push_str("#.......########");
-    map.push_str("#..............#");
-    map.push_str("#..............#");
-    map.push_str("################");
-    map.chars().collect()
-}
-
-fn is_wall(map: &[char], x: usize, y: usize) -> bool {
-    map[y * MAP_WIDTH + x] == '#'
-}
-
-fn draw_map(screen: &mut [u16], player: &Player, map: &[char]) {
-    for nx in 0..MAP_WIDTH {
-        for ny in 0..MAP_HEIGHT {
-            screen[(ny + 1) * SCREEN_WIDTH + nx] =
-                if player.y as usize == ny && player.x as usize == nx {
-                    'P' as u16
-                } else {
-                    map[ny * MAP_WIDTH + nx] as u16
+
+        screen.string(0, 0, &stats, palette.text);
+        if self.time.paused() {
+            // The full player state, for comparing it from one step to the next
+            let player = &self.player;
+            let state = format!(
+                "PAUSED (F6 to step) PLAYER X={}, Y={}, A={}, TURNING={}",
+                player.position.x, player.position.y, player.angle, player.turning
+            );
+            screen.string(0, 1, &state, palette.highlight);
+        }
+        if let Some((text, shown)) = &mut self.notice {
+            if shown.tick(self.time.real_delta()) == 0 && !shown.finished() {
+                screen.string(0, SCREEN_HEIGHT as i32 - 1, text, palette.highlight);
+            }
+        }
+
+        draw_hud(screen, &self.player, &self.locale, palette);
+        draw_map(
+            screen,
+            &self.player,
+            self.debug.spectator.as_ref(),
+            &self.map,
+            palette,
+        );
+        self.objectives
+            .draw(screen, self.map.height() + 2, &self.locale, palette);
+        if let Some(trial) = &self.time_trial {
+            let font = self.assets.get(self.font);
+            trial.draw(
+                screen,
+                font,
+                self.objectives.elapsed(),
+                &self.locale,
+                palette,
+            );
+        }
+        if let Some(dialogue) = &self.dialogue {
+            dialogue.draw(screen, &self.signs, &self.locale, palette);
+        }
+        if take_screenshot {
+            let text = match screenshot::save(&self.settings.data_dir, screen.cells()) {
+                Ok(path) => self
+                    .locale
+                    .format("notice.saved", &[("path", &path.display())]),
+                Err(e) => {
+                    warn!(error = %e, "saving a screenshot failed");
+                    self.locale
+                        .format("notice.screenshot_failed", &[("error", &e)])
+                }
+            };
+            self.notify(text);
+        }
+        if let Some(frames) = self.recording.as_mut() {
+            if !frames.capture(screen.cells()) || toggle_recording {
+                let text = match frames.save(&self.settings.data_dir) {
+                    Ok(path) => self
+                        .locale
+                        .format("notice.saved", &[("path", &path.display())]),
+                    Err(e) => {
+                        warn!(error = %e, "saving a recording failed");
+                        self.locale
+                            .format("notice.recording_failed", &[("error", &e)])
+                    }
                 };
+                self.notify(text);
+                self.recording = None;
+            }
+        } else if toggle_recording {
+            self.recording = Some(Recording::new());
+            self.notify(self.locale.get("notice.recording").to_string());
         }
+        State::Playing
     }
 }
 
-fn handle_controls(player: &mut Player, delta_time: f32, map: &[char]) {
-    let rotation_speed = 0.75;
-    let move_speed = 5.0;
-    unsafe {
-        if GetAsyncKeyState('A' as i32) != 0 {
-            player.a -= move_speed * rotation_speed * delta_time;
-        }
-        if GetAsyncKeyState('D' as i32) != 0 {
-            player.a += move_speed * rotation_speed * delta_time;
+impl ConsoleGameEngine for Game {
+    fn on_update(&mut self, console: &mut Console, delta_time: f32) -> bool {
+        if !console.focused() && !self.time.paused() {
+            self.time.set_paused(true);
+            self.paused_by_focus = true;
+        } else if console.focused() && self.paused_by_focus {
+            self.time.set_paused(false);
+            self.paused_by_focus = false;
         }
-        if GetAsyncKeyState('W' as i32) != 0 {
-            let x_offset = player.a.sin() * move_speed * delta_time;
-            let y_offset = player.a.cos() * move_speed * delta_time;
-            player.x += x_offset;
-            player.y += y_offset;
-            if is_wall(map, player.x as usize, player.y as usize) {
-                player.x -= x_offset;
-                player.y -= y_offset;
+        self.time.update(delta_time);
+        let _frame = trace_span!("frame", tick = self.time.ticks()).entered();
+        self.reload_assets();
+        let keys = console.keys().clone();
+        let mouse = *console.mouse();
+        let clicked = mouse.pressed(MouseButton::Left);
+        self.actions.update(&keys, self.time.real_delta());
+        self.menu_actions.update(&keys, self.time.real_delta());
+        let screen_before = std::mem::discriminant(&self.state);
+        self.state = match std::mem::replace(&mut self.state, State::Playing) {
+            State::Title(mut title) => {
+                if self.actions.take(Action::Palette) {
+                    self.next_palette();
+                }
+                title.update(self.time.real_delta());
+                title.draw(console.screen_mut(), self.settings.palette);
+                let (_, mouse_y) = mouse.position();
+                let clicked_line = Some(mouse_y)
+                    .filter(|_| clicked)
+                    .and_then(|y| title.prompt_line_at(y));
+                if self.menu_actions.take(MenuAction::Up) {
+                    title.select((title.selected() + TITLE_CHOICES - 1) % TITLE_CHOICES);
+                }
+                if self.menu_actions.take(MenuAction::Down) {
+                    title.select((title.selected() + 1) % TITLE_CHOICES);
+                }
+                let confirmed =
+                    self.actions.take(Action::Confirm) | self.menu_actions.take(MenuAction::Select);
+                let picked = match clicked_line {
+                    Some(line) if line < TITLE_CHOICES => Some(line),
+                    _ => Some(title.selected()).filter(|_| confirmed),
+                };
+                if picked == Some(0) {
+                    let cutscene = CutscenePlayer::new(intro_cutscene());
+                    self.cutscene = Some(cutscene.with_cuts(self.settings.reduced_motion));
+                    self.stats.games_started += 1;
+                    self.save_stats();
+                    State::Playing
+                } else if picked == Some(1) {
+                    State::Stats(title)
+                } else if picked == Some(2) {
+                    State::Options(title, options_menu(&self.settings))
+                } else {
+                    State::Title(title)
+                }
+            }
+            State::Stats(title) => {
+                self.stats
+                    .draw(console.screen_mut(), &self.locale, self.settings.palette);
+                let back = self.menu_actions.take(MenuAction::Back)
+                    | self.menu_actions.take(MenuAction::Select)
+                    | self.actions.take(Action::Confirm);
+                match clicked || back {
+                    true => State::Title(title),
+                    false => State::Stats(title),
+                }
             }
+            State::Options(title, mut menu) => {
+                let event = menu.update(&mut self.menu_actions, &keys);
+                menu.draw(console.screen_mut(), &self.locale, self.settings.palette);
+                match event {
+                    Some(menu::Event::Changed(i)) => {
+                        self.apply_option(&menu.items()[i]);
+                        State::Options(title, menu)
+                    }
+                    Some(menu::Event::Selected(i)) if menu.items()[i].id == "controls" => {
+                        State::Controls(title, menu, controls_menu(&self.actions))
+                    }
+                    Some(menu::Event::Back) | Some(menu::Event::Selected(_)) => {
+                        let prompt = title_prompt(&self.locale, &self.actions);
+                        let title = title.with_prompt(prompt);
+                        State::Title(title.with_reduced_motion(self.settings.reduced_motion))
+                    }
+                    None => State::Options(title, menu),
+                }
+            }
+            State::Controls(title, options, mut menu) => {
+                let event = menu.update(&mut self.menu_actions, &keys);
+                if let Some(menu::Event::Changed(i)) = event {
+                    self.rebind(&mut menu, i);
+                }
+                menu.draw(console.screen_mut(), &self.locale, self.settings.palette);
+                match event {
+                    Some(menu::Event::Back) | Some(menu::Event::Selected(_)) => {
+                        State::Options(title, options)
+                    }
+                    _ => State::Controls(title, options, menu),
+                }
+            }
+            State::Playing => self.play(console),
+            State::Intermission(mut intermission) => {
+                let entered = intermission.update(&keys, &mut self.leaderboard);
+                if entered {
+                    if let Err(e) = self
+                        .leaderboard
+                        .save(&leaderboard::path(&self.settings.data_dir))
+                    {
+                        warn!(error = %e, "saving the leaderboard failed");
+                        let text = self
+                            .locale
+                            .format("notice.leaderboard_failed", &[("error", &e)]);
+                        self.notify(text);
+                    }
+                }
+                let screen = console.screen_mut();
+                intermission.draw(
+                    screen,
+                    &self.leaderboard,
+                    &self.locale,
+                    self.settings.palette,
+                );
+                // The key confirming the name shouldn't also close the table
+                match !entered && intermission.done(&keys, &mouse) {
+                    true => {
+                        self.restart();
+                        State::Playing
+                    }
+                    false => State::Intermission(intermission),
+                }
+            }
+        };
+        if std::mem::discriminant(&self.state) != screen_before {
+            self.actions.clear_buffer();
+            self.menu_actions.clear_buffer();
         }
-        if GetAsyncKeyState('S' as i32) != 0 {
-            let x_offset = player.a.sin() * move_speed * delta_time;
-            let y_offset = player.a.cos() * move_speed * delta_time;
-            player.x -= x_offset;
-            player.y -= y_offset;
-            if is_wall(map, player.x as usize, player.y as usize) {
-                player.x += x_offset;
-                player.y += y_offset;
+        let real_delta = self.time.real_delta();
+        if let Some(timer) = self.title_status.as_mut() {
+            if timer.tick(real_delta) > 0 {
+                console.set_title(self.status());
             }
         }
+        let idle = self.time.paused() || !matches!(self.state, State::Playing);
+        console.set_max_fps(Some(IDLE_FPS).filter(|_| idle));
+        true
     }
 }
 
-fn update_screen(screen: &mut [u16], player: &Player, map: &[char]) {
-    for x in 0..SCREEN_WIDTH {
-        let ray_angle = (player.a - FOV / 2.0) + (x as f32 / SCREEN_WIDTH as f32) * FOV;
-        let mut distance_to_wall = 0.0;
-        let mut boundary = false;
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("config") => process::exit(settings::command(&args[1..])),
+        Some("map") => process::exit(map_report::command(&args[1..])),
+        _ => {}
+    }
 
-        let eye_x = ray_angle.sin();
-        let eye_y = ray_angle.cos();
-        loop {
-            distance_to_wall += 0.1;
+    // Standard error still points at the original screen buffer, which shows again once the
+    // game exits, so the message isn't drawn over the game
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        error!(%info, "the game crashed");
+        eprintln!("The game crashed, sorry about that. {}", info);
+        if env::var_os("RUST_BACKTRACE").is_some() {
+            default_hook(info);
+        }
+    }));
 
-            let test_x = (player.x + eye_x * distance_to_wall) as i32;
-            let test_y = (player.y + eye_y * distance_to_wall) as i32;
+    let result =
+        Game::new().and_then(|mut game| olc_fps::run(&mut game, SCREEN_WIDTH, SCREEN_HEIGHT));
+    if let Err(e) = result {
+        let message = error_chain(&e);
+        error!(error = %message, "the game stopped");
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
 
-            if test_x < 0 || test_x >= MAP_WIDTH as i32 || test_y < 0 || test_y >= MAP_HEIGHT as i32
-            {
-                distance_to_wall = DEPTH;
-                break;
-            } else if is_wall(map, test_x as usize, test_y as usize) {
-                let mut p: Vec<(f32, f32)> = Vec::new();
-                for tx in 0..2 {
-                    for ty in 0..2 {
-                        let vy = test_y as f32 + ty as f32 - player.y;
-                        let vx = test_x as f32 + tx as f32 - player.x;
-                        let d = (vx * vx + vy * vy).sqrt();
-                        let dot = (eye_x * vx / d) + (eye_y * vy / d);
-                        p.push((d, dot));
-                    }
+/// An error followed by what caused it, e.g. `couldn't load maps/start.map: ...`.
+fn error_chain(error: &dyn Error) -> String {
+    let mut text = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        text.push_str(": ");
+        text.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    text
+}
+
+/// Where to look for asset files, the last ones override the first ones: the data installed
+/// next to the executable, the `assets` directory in the working directory, then `mods`.
+fn asset_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(exe_dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+    {
+        dirs.push(exe_dir.join("assets"));
+    }
+    dirs.push(PathBuf::from("assets"));
+    dirs.push(PathBuf::from("mods"));
+    dirs
+}
+
+/// The `language` asked for, falling back to the language without its region, then to
+/// English. Only English has to exist.
+fn load_lang(assets: &mut Assets, wanted: &str) -> Result<Vec<Handle<StringTable>>, GameError> {
+    let mut chain = vec![wanted, wanted.split('_').next().unwrap_or_default()];
+    chain.retain(|lang| !lang.is_empty() && *lang != "en");
+    chain.dedup();
+
+    let mut tables = Vec::new();
+    for lang in chain {
+        match assets.load::<StringTable>(format!("lang/{}.lang", lang)) {
+            Ok(table) => tables.push(table),
+            Err(e) => debug!(lang, error = %e, "no translation loaded"),
+        }
+    }
+    tables.push(assets.load::<StringTable>("lang/en.lang")?);
+    Ok(tables)
+}
+
+/// The strings of the language files `lang`, as they are now.
+fn locale_of(assets: &Assets, lang: &[Handle<StringTable>]) -> Locale {
+    Locale::new(
+        lang.iter()
+            .map(|table| assets.get(*table).clone())
+            .collect(),
+    )
+}
+
+/// The choices of the title screen, then how to pick one with the key confirming.
+fn title_prompt(locale: &Locale, actions: &Actions<Action>) -> String {
+    let confirm = actions.keys(Action::Confirm).next().map(controls::key_name);
+    let confirm = confirm.unwrap_or_default();
+    format!(
+        "{}\n{}\n{}\n\n{}",
+        locale.get("title.start"),
+        locale.get("title.stats"),
+        locale.get("title.options"),
+        locale.format("title.prompt", &[("key", &confirm)])
+    )
+}
+
+/// The settings that can be changed in game, as they are now.
+fn options_menu(settings: &Settings) -> Menu {
+    let weather = WEATHER_CHOICES
+        .iter()
+        .position(|(kind, ..)| *kind == settings.weather)
+        .unwrap_or(0);
+    let palette = Palette::ALL
+        .iter()
+        .position(|p| std::ptr::eq(*p, settings.palette))
+        .unwrap_or(0);
+    let mut name = TextInput::new(leaderboard::MAX_NAME_LENGTH);
+    name.text = settings.name.clone();
+    let slider = |radians: f32, min, max, step| Widget::Slider {
+        value: radians.to_degrees().round(),
+        min,
+        max,
+        step,
+    };
+    Menu::new(
+        "options.title",
+        vec![
+            Item::new(
+                "reduced_motion",
+                "options.reduced_motion",
+                Widget::Toggle(settings.reduced_motion),
+            ),
+            Item::new("fov", "options.fov", slider(settings.fov, 30.0, 150.0, 5.0)),
+            Item::new(
+                "turn_speed",
+                "options.turn_speed",
+                slider(settings.turn_speed, 45.0, 720.0, 15.0),
+            ),
+            Item::new(
+                "weather",
+                "options.weather",
+                Widget::Choice {
+                    options: WEATHER_CHOICES.iter().map(|(_, _, label)| *label).collect(),
+                    selected: weather,
+                },
+            ),
+            Item::new(
+                "palette",
+                "options.palette",
+                Widget::Choice {
+                    options: Palette::ALL.iter().map(|p| p.name).collect(),
+                    selected: palette,
+                },
+            ),
+            Item::new(
+                "name",
+                "options.name",
+                Widget::Text {
+                    input: name,
+                    editing: false,
+                },
+            ),
+            Item::new("controls", "options.controls", Widget::Button),
+            Item::new("back", "options.back", Widget::Button),
+        ],
+    )
+}
+
+/// A key capture for every action, in the order of [`Action::ALL`].
+fn controls_menu(actions: &Actions<Action>) -> Menu {
+    let mut items: Vec<Item> = Action::ALL
+        .iter()
+        .map(|action| {
+            let keys = actions.keys(*action).collect();
+            let widget = Widget::KeyCapture {
+                keys,
+                capturing: false,
+            };
+            Item::new(action.key(), action.name(), widget)
+        })
+        .collect();
+    items.push(Item::new("back", "controls.back", Widget::Button));
+    Menu::new("controls.title", items)
+}
+
+/// The signs from the map's `@sign` lines, see [`Sign::parse`].
+fn init_signs(map: &Map) -> Vec<Sign> {
+    let signs = map.properties("sign").filter_map(|value| {
+        let sign = Sign::parse(value);
+        if sign.is_none() {
+            warn!(value, "skipping a sign that couldn't be read");
+        }
+        sign
+    });
+    signs.collect()
+}
+
+/// The cameras from the map's `@camera` lines, like `@camera camera.exit 1.5 14.5 90` for
+/// the name, a key into the `Locale`, the position and the angle in degrees.
+fn init_cameras(map: &Map) -> Vec<SecurityCamera> {
+    let camera = |value: &str| {
+        let mut words = value.split_whitespace();
+        let name = words.next()?.to_string();
+        let mut number = || words.next()?.parse::<f32>().ok().filter(|n| n.is_finite());
+        let position = Vec2::new(number()?, number()?);
+        let angle = number()?.to_radians();
+        let camera = Camera {
+            position,
+            angle,
+            fov: FOV * 1.5,
+            pitch: 0.0,
+        };
+        Some(SecurityCamera { name, camera })
+    };
+    let cameras = map.properties("camera").filter_map(|value| {
+        let found = camera(value);
+        if found.is_none() {
+            warn!(value, "skipping a camera that couldn't be read");
+        }
+        found
+    });
+    cameras.collect()
+}
+
+fn intro_cutscene() -> Cutscene {
+    Cutscene {
+        keyframes: vec![
+            Keyframe {
+                time: 0.0,
+                position: Vec2::new(1.5, 1.5),
+                angle: std::f32::consts::FRAC_PI_4,
+            },
+            Keyframe {
+                time: 4.0,
+                position: Vec2::new(6.0, 4.0),
+                angle: std::f32::consts::FRAC_PI_2,
+            },
+            Keyframe {
+                time: 8.0,
+                position: Vec2::new(8.0, 8.0),
+                angle: 0.0,
+            },
+        ],
+        captions: vec![
+            Caption {
+                start: 0.5,
+                end: 4.0,
+                text: "cutscene.intro.1",
+            },
+            Caption {
+                start: 4.5,
+                end: 8.0,
+                text: "cutscene.intro.2",
+            },
+        ],
+    }
+}
+
+/// Slowly looks around the player where they finished the level.
+fn ending_cutscene(player: &Player) -> Cutscene {
+    let third = 2.0 * std::f32::consts::PI / 3.0;
+    Cutscene {
+        keyframes: (0..4)
+            .map(|i| Keyframe {
+                time: i as f32 * 1.5,
+                position: player.position,
+                angle: player.angle + i as f32 * third,
+            })
+            .collect(),
+        captions: vec![Caption {
+            start: 0.0,
+            end: 4.5,
+            text: "cutscene.ending",
+        }],
+    }
+}
+
+/// The objectives from the map's `@objective` lines, see [`Objective::parse`]. Reaching
+/// the exit when it has none.
+fn init_objectives(map: &Map) -> Vec<Objective> {
+    let objectives = map.properties("objective").filter_map(|value| {
+        let objective = Objective::parse(value);
+        if objective.is_none() {
+            warn!(value, "skipping an objective that couldn't be read");
+        }
+        objective
+    });
+    let mut objectives: Vec<Objective> = objectives.collect();
+    if objectives.is_empty() {
+        let goal = Goal::ReachExit { time_limit: None };
+        objectives.push(Objective::new("objectives.reach_exit", goal, true));
+    }
+    objectives
+}
+
+/// Whether moving in a straight line from `from` to `to` touches a `tile`. Checking the end
+/// only would let a long step, like after a frame hitch, skip right over it.
+fn passes_over(map: &Map, from: Vec2, to: Vec2, tile: char) -> bool {
+    // A quarter of a tile apart, samples can only miss a tile by cutting across its corner
+    let samples = (from.distance(to) / 0.25).ceil().max(1.0);
+    (0..=samples as u32).any(|i| {
+        let point = from.lerp(to, i as f32 / samples);
+        map.at(point.x, point.y) == Some(tile)
+    })
+}
+
+/// The player standing at the start of `map`, or on a random spot of it with `random_start`.
+fn starting_player(map: &Map, settings: &Settings) -> Player {
+    let mut player = Player {
+        position: START,
+        angle: 0.0,
+        turning: 0.0,
+        velocity: Vec2::ZERO,
+        turn: 0.0,
+        crouch: 0.0,
+        stamina: Stamina::default(),
+        wading: None,
+        air: 1.0,
+        last_dry: START,
+        effects: Effects::default(),
+    };
+    if settings.random_start {
+        if let Some((position, angle)) = random_start(map) {
+            player.place(position, angle);
+        }
+    }
+    player
+}
+
+/// A random spot on open floor, in the middle of its tile, and a random angle to face. For
+/// practicing a level from anywhere, and shaking out collision bugs.
+fn random_start(map: &Map) -> Option<(Vec2, f32)> {
+    let open: Vec<(usize, usize)> = (0..map.height())
+        .flat_map(|y| (0..map.width()).map(move |x| (x, y)))
+        .filter(|(x, y)| {
+            map.get(*x, *y).is_some_and(|tile| {
+                !map.is_solid(tile) && tile != movement::DEEP_WATER && tile != EXIT
+            }) && map.portal(*x, *y).is_none()
+        })
+        .collect();
+    // Hashers get random keys, which is all the randomness this needs
+    let random = RandomState::new().build_hasher().finish();
+    let (x, y) = *open.get(random as usize % open.len().max(1))?;
+    let angle = (random >> 32) as f32 / u32::MAX as f32 * TAU;
+    Some((Vec2::new(x as f32 + 0.5, y as f32 + 0.5), angle))
+}
+
+/// Map coordinates of the sign right in front of the player, if any.
+fn facing_sign(player: &Player, map: &Map) -> Option<(usize, usize)> {
+    raycast(map, player.position, direction(player.angle), 1.0)
+        .filter(|hit| hit.tile == SIGN)
+        .map(|hit| hit.tile_coords)
+}
+
+fn draw_map(
+    screen: &mut Screen,
+    player: &Player,
+    spectator: Option<&Player>,
+    map: &Map,
+    palette: &Palette,
+) {
+    let is_at = |p: &Player, nx: usize, ny: usize| {
+        p.position.y as usize == ny && p.position.x as usize == nx
+    };
+    for nx in 0..map.width() {
+        for ny in 0..map.height() {
+            let (c, color) = if is_at(player, nx, ny) {
+                ('P', palette.map_player)
+            } else if spectator.is_some_and(|s| is_at(s, nx, ny)) {
+                ('C', palette.map_marker)
+            } else {
+                match map.get(nx, ny) {
+                    Some(tile) if map.is_solid(tile) => (tile, palette.map_wall),
+                    Some(tile) => (tile, palette.map_floor),
+                    None => (' ', palette.background),
                 }
+            };
+            screen.set_cell(nx as i32, ny as i32 + 1, c, color);
+        }
+    }
+}
 
-                p.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or_else(|| Ordering::Equal));
+/// Stamina, air while it's running low, whether the player is crouched and the powerups
+/// in effect with their seconds left, stacked up from the bottom center of the screen.
+fn draw_hud(screen: &mut Screen, player: &Player, locale: &Locale, palette: &Palette) {
+    let color = match player.stamina.exhausted() {
+        true => palette.highlight,
+        false => palette.text,
+    };
+    let mut y = SCREEN_HEIGHT as i32 - 1;
+    let stamina = meter(locale.get("hud.stamina"), player.stamina.level);
+    screen.centered_string(y, &stamina, color);
+    if player.air < 1.0 {
+        y -= 1;
+        let air = meter(locale.get("hud.air"), player.air);
+        screen.centered_string(y, &air, palette.highlight);
+    }
+    if player.crouch >= 1.0 {
+        y -= 1;
+        screen.centered_string(y, locale.get("hud.crouched"), palette.text);
+    }
+    for (powerup, remaining) in player.effects.iter() {
+        y -= 1;
+        let text = format!("{} {:.0}", locale.get(powerup.name()), remaining.ceil());
+        screen.centered_string(y, &text, palette.highlight);
+    }
+}
+
+/// A labelled bar filled up to `level`, from 0 to 1.
+fn meter(label: &str, level: f32) -> String {
+    const WIDTH: usize = 10;
+    let filled = (level.clamp(0.0, 1.0) * WIDTH as f32).round() as usize;
+    format!(
+        "{} {}{}",
+        label,
+        "\u{2588}".repeat(filled),
+        "\u{2591}".repeat(WIDTH - filled)
+    )
+}
+
+/// Renders what's behind the camera in a small framed mirror.
+fn draw_rear_view(
+    screen: &mut Screen,
+    camera: &Camera,
+    map: &Map,
+    ambience: &Ambience,
+    label: &str,
+    palette: &Palette,
+) {
+    let behind = Camera {
+        angle: camera.angle + std::f32::consts::PI,
+        ..*camera
+    };
+    draw_framed_view(
+        screen,
+        &behind,
+        map,
+        ambience,
+        &Viewport::REAR_VIEW,
+        label,
+        palette,
+    );
+}
+
+/// Renders a view inside a frame with a label on its top edge.
+fn draw_framed_view(
+    screen: &mut Screen,
+    camera: &Camera,
+    map: &Map,
+    ambience: &Ambience,
+    view: &Viewport,
+    label: &str,
+    palette: &Palette,
+) {
+    let (left, top) = (view.left as i32 - 1, view.top as i32 - 1);
+    let (width, height) = (view.width as i32 + 2, view.height as i32 + 2);
+    screen.frame(left, top, width, height, &Border::SINGLE, palette.frame);
+    screen.aligned_string(left + 2, top, width - 4, label, Align::Left, palette.text);
+    render_view(screen, camera, map, ambience, view, palette);
+}
+
+fn debug_key(keys: &Keys) -> Option<DebugCommand> {
+    if keys.pressed(keys::F1) {
+        Some(DebugCommand::Noclip)
+    } else if keys.pressed(keys::F2) {
+        Some(DebugCommand::Spectator)
+    } else {
+        None
+    }
+}
+
+fn handle_dialogue_keys(
+    keys: &Keys,
+    actions: &mut Actions<Action>,
+    dialogue: Dialogue,
+    signs: &[Sign],
+) -> Option<Dialogue> {
+    if actions.take(Action::Confirm) {
+        return dialogue.advance(signs, dialogue::Input::Continue);
+    }
+    for (index, key) in (b'1'..=b'9').enumerate() {
+        if keys.pressed(key as i32) {
+            return dialogue.advance(signs, dialogue::Input::Choose(index));
+        }
+    }
+    Some(dialogue)
+}
+
+/// Eases the turning axis towards `target`, speeding up more gently than it slows down.
+fn ease_axis(current: f32, target: f32, delta_time: f32) -> f32 {
+    let speeding_up = target.abs() > current.abs() && target * current >= 0.0;
+    let rate = match speeding_up {
+        true => AXIS_ACCELERATION,
+        false => AXIS_DECELERATION,
+    };
+    approach(current, target, rate * delta_time)
+}
+
+fn handle_controls(
+    player: &mut Player,
+    actions: &mut Actions<Action>,
+    delta_time: f32,
+    map: &Map,
+    noclip: bool,
+    settings: &Settings,
+) {
+    let turn = actions.axis(Action::TurnLeft, Action::TurnRight);
+    match settings.snap_turn {
+        Some(step) => {
+            // Taken rather than checked, so each press turns once, on a tick that runs
+            let mut pressed = |action| actions.take(action) as i32 as f32;
+            let right = pressed(Action::TurnRight);
+            player.angle += (right - pressed(Action::TurnLeft)) * step;
+        }
+        None => {
+            player.turning = if turn == 0.0 {
+                0.0
+            } else {
+                player.turning + delta_time
+            };
+            player.turn = ease_axis(player.turn, turn, delta_time);
+            let speed = settings.turn_speed * settings.turn_curve.factor(player.turning);
+            player.angle += player.turn * speed * delta_time;
+        }
+    }
+    player.angle = wrap_angle(player.angle);
+
+    let walk = actions.axis(Action::Back, Action::Forward);
+    let crouching = actions.held(Action::Crouch);
+    player.crouch = movement::crouch(player.crouch, crouching, delta_time);
+    let sprint = actions.held(Action::Sprint) && walk > 0.0 && !crouching;
+    let speed = match player.stamina.update(sprint, delta_time) {
+        true => movement::SPRINT_FACTOR,
+        false if crouching => movement::CROUCH_FACTOR,
+        false => 1.0,
+    };
+    let speed = match player.effects.active(Powerup::SpeedBoost) {
+        true => speed * powerups::SPEED_BOOST_FACTOR,
+        false => speed,
+    };
+    let surface = match tile_at(map, player.position) {
+        Some(movement::ICE) => Surface::ICE,
+        Some(movement::WATER) | Some(movement::DEEP_WATER) => Surface::WATER,
+        _ => settings.ground,
+    };
+    let surface = surface.with_speed(speed);
+    let wish = direction(player.angle) * walk;
+    player.velocity = movement::accelerate(player.velocity, wish, &surface, delta_time);
+    let step = player.velocity * delta_time;
+    let before = player.position;
+    if noclip {
+        // Walls don't stop us anymore but the map edge still does.
+        let edge = Vec2::new(map.width() as f32, map.height() as f32) - 0.01;
+        player.position = (player.position + step).clamp(Vec2::ZERO, edge);
+    } else {
+        player.position = collision::slide(map, &PLAYER_BODY, player.position, step);
+    }
+    // Whatever a wall stopped is lost, so sliding along it keeps only the speed along it
+    if delta_time > 0.0 {
+        player.velocity = (player.position - before) / delta_time;
+    }
+
+    let tile = tile_at(map, player.position);
+    let in_water = matches!(tile, Some(movement::WATER) | Some(movement::DEEP_WATER));
+    player.wading = match in_water {
+        true => Some(player.wading.unwrap_or(0.0) + delta_time),
+        false => None,
+    };
+    if !in_water {
+        player.last_dry = player.position;
+    }
+    let submerged = tile == Some(movement::DEEP_WATER);
+    player.air = movement::breathe(player.air, submerged, delta_time);
+}
+
+/// The time of day the map is locked to with an `@time_of_day` line, as a fraction of a
+/// day from midnight.
+fn time_of_day(map: &Map) -> Option<f32> {
+    map.property("time_of_day")?.parse().ok()
+}
+
+/// Tells `map` what each of the game's tiles does.
+fn with_tile_kinds(map: Map, settings: &Settings) -> Map {
+    map.with_solid(&SOLID_TILES)
+        .with_see_through(&SEE_THROUGH_TILES)
+        .with_segments(&SEGMENTS)
+        .with_portals(&PORTALS)
+        .with_mirrors(&[MIRROR], settings.mirror_bounces)
+}
+
+/// Where a body going from `from` to `to` ends up if that took it into a portal.
+fn portal_exit(map: &Map, from: Vec2, to: Vec2) -> Option<Vec2> {
+    let (x, y) = (to.x.floor(), to.y.floor());
+    if (x, y) == (from.x.floor(), from.y.floor()) || x < 0.0 || y < 0.0 {
+        return None;
+    }
+    let (exit_x, exit_y) = map.portal(x as usize, y as usize)?;
+    Some(to + Vec2::new(exit_x as f32 - x, exit_y as f32 - y))
+}
+
+fn tile_at(map: &Map, position: Vec2) -> Option<char> {
+    map.get(position.x as usize, position.y as usize)
+}
+
+fn update_screen(
+    screen: &mut Screen,
+    camera: &Camera,
+    map: &Map,
+    ambience: &Ambience,
+    palette: &Palette,
+) -> Vec<f32> {
+    render_view(
+        screen,
+        camera,
+        map,
+        ambience,
+        &Viewport::FULL_SCREEN,
+        palette,
+    )
+}
+
+/// Draws falling weather over the full screen view, hiding particles behind walls using
+/// the `depth` of each column.
+fn draw_weather(
+    screen: &mut Screen,
+    camera: &Camera,
+    depth: &[f32],
+    weather: &Weather,
+    palette: &Palette,
+) {
+    let view = &Viewport::FULL_SCREEN;
+    let view_height = view.height as f32;
+    let horizon = view_height * (0.5 + camera.pitch);
+    let color = match weather.kind {
+        weather::Kind::Rain => palette.water,
+        weather::Kind::Snow => palette.text,
+    };
+    for particle in weather.particles() {
+        let offset = particle.position - camera.position;
+        let distance = offset.length();
+        let angle = wrap_angle(offset.x.atan2(offset.y) - camera.angle);
+        let column = ((angle / camera.fov + 0.5) * view.width as f32).floor();
+        if distance < 0.2 || column < 0.0 || column >= view.width as f32 {
+            continue;
+        }
+        if depth
+            .get(column as usize)
+            .is_some_and(|wall| distance < *wall)
+        {
+            let y = (horizon - particle.height * view_height / distance) as i32;
+            let c = weather.kind.glyph(distance);
+            screen.set_cell(
+                view.left as i32 + column as i32,
+                view.top as i32 + y,
+                c,
+                color,
+            );
+        }
+    }
+}
+
+/// The ripple drawn for the floor `below` rows under the horizon, if that's water. Floor
+/// rows map back to distances the same way walls do, so the floor lines up with them.
+fn water_at(
+    map: &Map,
+    camera: &Camera,
+    eye: Vec2,
+    below: f32,
+    view_height: f32,
+    time: f32,
+) -> Option<char> {
+    if below <= 0.0 {
+        return None;
+    }
+    let point = camera.position + eye * (view_height / below);
+    match tile_at(map, point) {
+        Some(movement::WATER) | Some(movement::DEEP_WATER) => {}
+        _ => return None,
+    }
+    let wave = ((point.x + point.y) * 3.0 + time * 2.0).sin() + (point.x * 5.0 - time).sin();
+    Some(match wave {
+        w if w > 0.8 => '~',
+        w if w > -0.8 => '-',
+        _ => ' ',
+    })
+}
+
+/// Raycasts the scene as seen from `camera` into a rectangle of the screen, in the light of
+/// `ambience`. Returns how far the wall drawn in each column is, for
+/// drawing things in front of walls but behind nearer ones.
+fn render_view(
+    screen: &mut Screen,
+    camera: &Camera,
+    map: &Map,
+    ambience: &Ambience,
+    view: &Viewport,
+    palette: &Palette,
+) -> Vec<f32> {
+    let mut depth = Vec::with_capacity(view.width);
+    let view_height = view.height as f32;
+    let horizon = view_height * (0.5 + camera.pitch);
+    for x in 0..view.width {
+        let ray_angle =
+            (camera.angle - camera.fov / 2.0) + (x as f32 / view.width as f32) * camera.fov;
+        let eye = direction(ray_angle);
+        let mut see_through = Vec::new();
+        let hit = ray_hits(map, camera.position, eye, DEPTH).find(|hit| {
+            let bounces = map.is_mirror(hit.tile) && hit.reflections < map.max_reflections();
+            let keep_going = bounces || map.is_see_through(hit.tile);
+            if keep_going {
+                see_through.push(*hit);
+            }
+            !keep_going
+        });
+        let reflections = hit.map_or(0, |hit| hit.reflections);
+        let (distance_to_wall, boundary) = match hit {
+            Some(hit) => {
+                // Leave the edges of tiles blank so walls stand out from each other, as seen
+                // from where the ray would have come from straight through portals and mirrors
+                let (tile_x, tile_y) = hit.tile_coords;
+                let origin = Vec2::new(tile_x as f32, tile_y as f32);
+                let eye = (hit.hit_point - hit.origin).normalize_or_zero();
+                let corners: Vec<Vec2> = match map.segment(hit.tile) {
+                    Some(segment) => vec![origin + segment.from, origin + segment.to],
+                    None => vec![origin, origin + Vec2::X, origin + Vec2::Y, origin + 1.0],
+                };
+                let mut p: Vec<(f32, f32)> = corners
+                    .iter()
+                    .map(|corner| {
+                        let v = *corner - hit.origin;
+                        (v.length(), eye.dot(v.normalize()))
+                    })
+                    .collect();
+
+                p.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
 
                 let bound = 0.01;
-                boundary = p[0].1.acos() < bound || p[1].1.acos() < bound;
-                break;
+                (hit.distance, p[0].1.acos() < bound || p[1].1.acos() < bound)
             }
-        }
+            None => (DEPTH, false),
+        };
+        depth.push(distance_to_wall);
 
-        let ceiling = (SCREEN_HEIGHT as f32 / 2.0 - SCREEN_HEIGHT as f32 / distance_to_wall) as i32;
-        let floor = SCREEN_HEIGHT as i32 - ceiling;
+        let ceiling = (horizon - view_height / distance_to_wall) as i32;
+        let floor = (horizon + view_height / distance_to_wall) as i32;
 
-        for y in 0..SCREEN_HEIGHT {
+        for y in 0..view.height {
+            let (screen_x, screen_y) = ((view.left + x) as i32, (view.top + y) as i32);
             let y = y as i32;
-            let x = x as i32;
-            let index = y * SCREEN_WIDTH as i32 + x;
 
-            screen[index as usize] = if y < ceiling {
-                ' ' as u16 // ceiling
+            let darkness = ambience.darkness;
+            let (c, color) = if y < ceiling {
+                sky(ray_angle, y, darkness, palette)
             } else if y > ceiling && y <= floor {
-                let wall = if boundary {
-                    ' '
-                } else {
-                    match distance_to_wall {
-                        d if d <= DEPTH / 4.0 => '\u{2588}',
-                        d if d < DEPTH / 3.0 => '\u{2593}',
-                        d if d < DEPTH / 2.0 => '\u{2592}',
-                        d if d < DEPTH => '\u{2591}',
-                        _ => ' ',
-                    }
-                };
-
-                wall as u16
+                match boundary {
+                    true => (' ', palette.background),
+                    false => wall_shade(distance_to_wall, reflections + darkness, palette),
+                }
+            } else if let Some(ripple) = water_at(
+                map,
+                camera,
+                eye,
+                y as f32 - horizon,
+                view_height,
+                if ambience.waves { ambience.time } else { 0.0 },
+            ) {
+                (ripple, palette.water)
             } else {
                 let floor_distance =
-                    1.0 - (y as f32 - SCREEN_HEIGHT as f32 / 2.0) / (SCREEN_HEIGHT as f32 / 2.0);
-                let floor = match floor_distance {
+                    1.0 - (y as f32 - horizon) / (view_height / 2.0) + darkness as f32 * 0.15;
+                let c = match floor_distance {
                     fd if fd < 0.25 => '#',
                     fd if fd < 0.5 => 'x',
                     fd if fd < 0.75 => '-',
                     fd if fd < 0.9 => '.',
                     _ => ' ',
                 };
-                floor as u16
+                (c, palette.floor)
             };
+            screen.set_cell(screen_x, screen_y, c, color);
+        }
+
+        // Bars, windows and mirror frames go over what's behind them, the nearest one last
+        for hit in see_through.iter().rev() {
+            let top = horizon - view_height / hit.distance;
+            let bottom = horizon + view_height / hit.distance;
+            let along = match hit.side {
+                Side::West | Side::East => hit.hit_point.y,
+                Side::North | Side::South => hit.hit_point.x,
+            }
+            .fract();
+            let rows = (top as i32 + 1).max(0)..=(bottom as i32).min(view.height as i32 - 1);
+            for y in rows {
+                let across = (y as f32 - top) / (bottom - top);
+                if covers(hit.tile, along, across) {
+                    let dim = hit.reflections + ambience.darkness;
+                    let (c, color) = wall_shade(hit.distance, dim, palette);
+                    let (screen_x, screen_y) = ((view.left + x) as i32, view.top as i32 + y);
+                    screen.set_cell(screen_x, screen_y, c, color);
+                }
+            }
         }
     }
+    depth
 }
 
-fn draw_screen_to_console(h_console: HANDLE, screen: &mut Vec<u16>, bytes_written: &mut u32) {
-    screen[SCREEN_SIZE - 1] = '\0' as u16;
-    unsafe {
-        WriteConsoleOutputCharacterW(
-            h_console,
-            &screen[0],
-            SCREEN_SIZE as u32,
-            COORD { X: 0, Y: 0 },
-            bytes_written,
-        );
+/// What the ceiling shows: nothing by day and a few stars at night, fixed in the sky as
+/// the view turns.
+fn sky(ray_angle: f32, y: i32, darkness: u32, palette: &Palette) -> (char, u16) {
+    let column = (ray_angle.rem_euclid(std::f32::consts::TAU) * 150.0) as u32;
+    let hash = column.wrapping_mul(2_654_435_761) ^ (y as u32).wrapping_mul(40_503);
+    match darkness >= 2 && hash % 37 == 0 {
+        true => ('.', palette.sky),
+        false => (' ', palette.background),
+    }
+}
+
+/// The block and color walls are drawn with at `distance`, fading out farther away and
+/// one shade more for each step of `dim`, like mirrors they're seen in or the dark.
+fn wall_shade(distance: f32, dim: u32, palette: &Palette) -> (char, u16) {
+    let [near, close, far, farthest] = palette.walls;
+    let shades = [
+        ('\u{2588}', near),
+        ('\u{2593}', close),
+        ('\u{2592}', far),
+        ('\u{2591}', farthest),
+    ];
+    let shade = match distance {
+        d if d <= DEPTH / 4.0 => 0,
+        d if d < DEPTH / 3.0 => 1,
+        d if d < DEPTH / 2.0 => 2,
+        d if d < DEPTH => 3,
+        _ => shades.len(),
+    };
+    shades
+        .get(shade + dim as usize)
+        .copied()
+        .unwrap_or((' ', palette.background))
+}
+
+/// Whether a see-through `tile` is opaque at a point of its face, `along` it from 0 to 1
+/// and `across` from its top to its bottom.
+fn covers(tile: char, along: f32, across: f32) -> bool {
+    let edge = |t: f32| (t - 0.5).abs() > 0.44;
+    match tile {
+        BARS => edge(across) || (along * 4.0).fract() < 0.25,
+        WINDOW => edge(across) || edge(along) || (across - 0.5).abs() < 0.03,
+        MIRROR => edge(across) || edge(along),
+        _ => true,
     }
 }
@@ -0,0 +1,261 @@
+use crate::{
+    assets::{self, Asset},
+    math::Vec2,
+};
+use std::io;
+use thiserror::Error;
+
+/// Why a map drawn as text couldn't be read.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MapError {
+    #[error("the map has no rows")]
+    Empty,
+    /// Counted from 1 with the property lines above, like lines in a text editor.
+    #[error("row {row} is {found} tiles wide instead of {expected}")]
+    UnevenRow {
+        row: usize,
+        found: usize,
+        expected: usize,
+    },
+}
+
+/// A wall that only runs along a line through its tile instead of filling it, like a thin
+/// wall or a diagonal one. The ends are in tile units, from `(0, 0)` at the tile's lowest
+/// corner to `(1, 1)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub from: Vec2,
+    pub to: Vec2,
+}
+
+impl Segment {
+    pub const fn new(from: Vec2, to: Vec2) -> Self {
+        Segment { from, to }
+    }
+
+    /// The point of the segment closest to `point`, both relative to the tile.
+    pub fn closest(&self, point: Vec2) -> Vec2 {
+        let along = self.to - self.from;
+        let t = (point - self.from).dot(along) / along.length_squared().max(f32::EPSILON);
+        self.from + along * t.clamp(0.0, 1.0)
+    }
+}
+
+/// A grid of tiles, one character each, stored row by row.
+#[derive(Clone)]
+pub struct Map {
+    width: usize,
+    height: usize,
+    tiles: Vec<char>,
+    solid: Vec<char>,
+    see_through: Vec<char>,
+    segments: Vec<(char, Segment)>,
+    /// Coordinates of linked tiles.
+    portals: Vec<((usize, usize), (usize, usize))>,
+    mirrors: Vec<char>,
+    max_reflections: u32,
+    /// From `@key value` lines above the rows.
+    properties: Vec<(String, String)>,
+}
+
+impl Map {
+    /// Only `'#'` is solid until [`Map::with_solid`] says otherwise.
+    ///
+    /// Panics if there aren't exactly `width * height` tiles.
+    pub fn new(width: usize, height: usize, tiles: Vec<char>) -> Self {
+        assert_eq!(
+            tiles.len(),
+            width * height,
+            "the map isn't {}x{}",
+            width,
+            height
+        );
+        Map {
+            width,
+            height,
+            tiles,
+            solid: vec!['#'],
+            see_through: Vec::new(),
+            segments: Vec::new(),
+            portals: Vec::new(),
+            mirrors: Vec::new(),
+            max_reflections: 0,
+            properties: Vec::new(),
+        }
+    }
+
+    /// Reads a map drawn as text, one line per row. Fails if it's empty or if its rows
+    /// don't all have the same length.
+    ///
+    /// Lines starting with `@` before the rows set properties the game can read with
+    /// [`Map::property`] and [`Map::properties`], as `@key value`.
+    pub fn parse(source: &str) -> Result<Map, MapError> {
+        let mut properties = Vec::new();
+        let mut lines = source.lines().peekable();
+        while let Some(line) = lines.next_if(|line| line.starts_with('@')) {
+            let mut parts = line[1..].trim().splitn(2, char::is_whitespace);
+            let key = parts.next().unwrap_or_default().to_string();
+            let value = parts.next().unwrap_or_default().trim().to_string();
+            properties.push((key, value));
+        }
+        let rows: Vec<&str> = lines.map(str::trim_end).collect();
+        let rows = match rows.iter().rposition(|row| !row.is_empty()) {
+            Some(last) => &rows[..=last],
+            None => return Err(MapError::Empty),
+        };
+        let expected = rows[0].chars().count();
+        for (i, row) in rows.iter().enumerate() {
+            let found = row.chars().count();
+            if found != expected {
+                let row = properties.len() + i + 1;
+                return Err(MapError::UnevenRow {
+                    row,
+                    found,
+                    expected,
+                });
+            }
+        }
+        let tiles = rows.iter().flat_map(|row| row.chars()).collect();
+        Ok(Map {
+            properties,
+            ..Map::new(expected, rows.len(), tiles)
+        })
+    }
+
+    /// Sets which tiles block movement and rays.
+    pub fn with_solid(mut self, solid: &[char]) -> Self {
+        self.solid = solid.to_vec();
+        self
+    }
+
+    /// Sets which solid tiles the view goes through, like bars or windows. They still
+    /// block movement.
+    pub fn with_see_through(mut self, see_through: &[char]) -> Self {
+        self.see_through = see_through.to_vec();
+        self
+    }
+
+    /// Sets which solid tiles only block along a segment, the rest of the tile being open
+    /// floor.
+    pub fn with_segments(mut self, segments: &[(char, Segment)]) -> Self {
+        self.segments = segments.to_vec();
+        self
+    }
+
+    /// Links the two tiles of each of `tiles` together: going into one comes out of the
+    /// other, for rays and bodies alike. Tiles that aren't on the map exactly twice are left
+    /// as they are.
+    pub fn with_portals(mut self, tiles: &[char]) -> Self {
+        self.portals.clear();
+        for tile in tiles {
+            let found: Vec<(usize, usize)> = (0..self.tiles.len())
+                .filter(|i| self.tiles[*i] == *tile)
+                .map(|i| (i % self.width, i / self.width))
+                .collect();
+            if let [from, to] = found[..] {
+                self.portals.push((from, to));
+            }
+        }
+        self
+    }
+
+    /// Sets which solid tiles reflect rays, and how many times in a row a ray can bounce
+    /// before mirrors stop it like walls.
+    pub fn with_mirrors(mut self, mirrors: &[char], max_reflections: u32) -> Self {
+        self.mirrors = mirrors.to_vec();
+        self.max_reflections = max_reflections;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The tile at `(x, y)`, or `None` outside of the map.
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        if x < self.width && y < self.height {
+            Some(self.tiles[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// The tile under a position in map units.
+    pub fn at(&self, x: f32, y: f32) -> Option<char> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        self.get(x as usize, y as usize)
+    }
+
+    /// Changes the tile at `(x, y)`, like when something lying there gets picked up.
+    /// Does nothing outside of the map.
+    pub fn set(&mut self, x: usize, y: usize, tile: char) {
+        if x < self.width && y < self.height {
+            self.tiles[y * self.width + x] = tile;
+        }
+    }
+
+    /// Whether `tile` blocks movement and rays.
+    pub fn is_solid(&self, tile: char) -> bool {
+        self.solid.contains(&tile)
+    }
+
+    pub fn is_see_through(&self, tile: char) -> bool {
+        self.see_through.contains(&tile)
+    }
+
+    /// The segment `tile` is limited to, if it's solid and doesn't fill its whole tile.
+    pub fn segment(&self, tile: char) -> Option<Segment> {
+        let (_, segment) = self.segments.iter().find(|(t, _)| *t == tile)?;
+        Some(*segment).filter(|_| self.is_solid(tile))
+    }
+
+    /// The value of an `@key value` line from the map's source.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        let (_, value) = self.properties.iter().find(|(k, _)| k == key)?;
+        Some(value)
+    }
+
+    /// The values of every `@key value` line, in order, for properties a map can have
+    /// several of.
+    pub fn properties<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        let values = self.properties.iter().filter(move |(k, _)| k == key);
+        values.map(|(_, value)| value.as_str())
+    }
+
+    /// Whether rays bounce off `tile`. Only whole tiles do, not segments.
+    pub fn is_mirror(&self, tile: char) -> bool {
+        self.mirrors.contains(&tile) && self.is_solid(tile) && self.segment(tile).is_none()
+    }
+
+    pub fn max_reflections(&self) -> u32 {
+        self.max_reflections
+    }
+
+    /// Where the portal at `(x, y)` leads, if there's one.
+    pub fn portal(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        self.portals.iter().find_map(|(a, b)| match (x, y) {
+            at if at == *a => Some(*b),
+            at if at == *b => Some(*a),
+            _ => None,
+        })
+    }
+
+    /// Anything outside of the map counts as a wall, tiles limited to a segment don't since
+    /// a point can't be inside a line.
+    pub fn is_wall(&self, x: f32, y: f32) -> bool {
+        self.at(x, y)
+            .is_none_or(|tile| self.is_solid(tile) && self.segment(tile).is_none())
+    }
+}
+
+impl Asset for Map {
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Map::parse(assets::text(bytes)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
@@ -0,0 +1,493 @@
+use crate::{
+    dialogue::SIGN,
+    movement::{DEEP_WATER, ICE, WATER},
+    objectives::EXIT,
+    powerups::{NIGHT_VISION, SPEED_BOOST},
+    settings::{Config, Settings},
+    time_trial::{self, CHECKPOINT},
+    weather::OUTDOORS,
+    with_tile_kinds, EMBEDDED_ASSETS, START,
+};
+use olc_fps::Map;
+use std::{collections::VecDeque, fmt::Write, fs};
+
+/// What a tile is, as far as someone reviewing a level cares.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Feature {
+    Floor,
+    Wall,
+    /// Only blocks along a line through the tile.
+    ThinWall,
+    SeeThrough,
+    Mirror,
+    Sign,
+    Portal,
+    Exit,
+    Checkpoint,
+    Powerup,
+    Water,
+    DeepWater,
+    Ice,
+    Outdoors,
+}
+
+impl Feature {
+    /// In the order the legend lists them.
+    const ALL: [Feature; 14] = [
+        Feature::Exit,
+        Feature::Portal,
+        Feature::Checkpoint,
+        Feature::Powerup,
+        Feature::Sign,
+        Feature::Wall,
+        Feature::ThinWall,
+        Feature::SeeThrough,
+        Feature::Mirror,
+        Feature::Water,
+        Feature::DeepWater,
+        Feature::Ice,
+        Feature::Outdoors,
+        Feature::Floor,
+    ];
+
+    fn of(map: &Map, x: usize, y: usize) -> Feature {
+        let tile = map.get(x, y).unwrap_or('#');
+        match tile {
+            EXIT => Feature::Exit,
+            CHECKPOINT => Feature::Checkpoint,
+            SPEED_BOOST | NIGHT_VISION => Feature::Powerup,
+            SIGN => Feature::Sign,
+            WATER => Feature::Water,
+            DEEP_WATER => Feature::DeepWater,
+            ICE => Feature::Ice,
+            OUTDOORS => Feature::Outdoors,
+            _ if map.portal(x, y).is_some() => Feature::Portal,
+            _ if map.is_mirror(tile) => Feature::Mirror,
+            _ if map.segment(tile).is_some() => Feature::ThinWall,
+            _ if map.is_see_through(tile) => Feature::SeeThrough,
+            _ if map.is_solid(tile) => Feature::Wall,
+            _ => Feature::Floor,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Feature::Floor => "floor",
+            Feature::Wall => "wall",
+            Feature::ThinWall => "thin or diagonal wall",
+            Feature::SeeThrough => "bars or window",
+            Feature::Mirror => "mirror",
+            Feature::Sign => "sign",
+            Feature::Portal => "portal",
+            Feature::Exit => "exit",
+            Feature::Checkpoint => "time trial checkpoint",
+            Feature::Powerup => "powerup",
+            Feature::Water => "water",
+            Feature::DeepWater => "deep water",
+            Feature::Ice => "ice",
+            Feature::Outdoors => "outdoors",
+        }
+    }
+
+    /// Whether the legend lists where each one is, rather than only how many there are.
+    fn is_landmark(self) -> bool {
+        matches!(
+            self,
+            Feature::Exit
+                | Feature::Portal
+                | Feature::Checkpoint
+                | Feature::Powerup
+                | Feature::Sign
+        )
+    }
+
+    /// For the PNG overview.
+    #[cfg(feature = "png-screenshots")]
+    fn color(self) -> [u8; 3] {
+        match self {
+            Feature::Floor => [48, 48, 48],
+            Feature::Wall => [160, 160, 160],
+            Feature::ThinWall => [120, 120, 120],
+            Feature::SeeThrough => [110, 150, 190],
+            Feature::Mirror => [230, 230, 250],
+            Feature::Sign => [220, 200, 60],
+            Feature::Portal => [200, 60, 200],
+            Feature::Exit => [60, 220, 60],
+            Feature::Checkpoint => [240, 140, 30],
+            Feature::Powerup => [250, 250, 120],
+            Feature::Water => [40, 90, 200],
+            Feature::DeepWater => [20, 40, 120],
+            Feature::Ice => [150, 220, 230],
+            Feature::Outdoors => [50, 100, 40],
+        }
+    }
+}
+
+/// Marks where the player starts on the overview.
+const SPAWN: char = '@';
+
+/// Runs `map export`, which writes a top-down overview of a map with a legend of what's
+/// on it, or `map stats`, which sums up how it plays, for reviewing levels. Returns the
+/// exit code.
+pub fn command(args: &[String]) -> i32 {
+    let (command, path, output) = match args {
+        [command, rest @ ..] if command == "export" && rest.len() <= 2 => {
+            (command, rest.first(), rest.get(1))
+        }
+        [command, rest @ ..] if command == "stats" && rest.len() <= 1 => {
+            (command, rest.first(), None)
+        }
+        _ => {
+            eprintln!("Usage: olc_fps map export [MAP [OUTPUT]] | olc_fps map stats [MAP]");
+            return 2;
+        }
+    };
+    let map = match load(path.map(String::as_str)) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let name = path.map_or("the built-in map", String::as_str);
+    if command == "stats" {
+        print!("{}", Report::of(&map).to_text(name));
+        return 0;
+    }
+    let result = match output {
+        #[cfg(feature = "png-screenshots")]
+        Some(output) if output.ends_with(".png") => raster::save_png(&map, output.as_ref()),
+        #[cfg(not(feature = "png-screenshots"))]
+        Some(output) if output.ends_with(".png") => {
+            eprintln!("PNG overviews need the game built with the png-screenshots feature");
+            return 1;
+        }
+        Some(output) => fs::write(output, overview(&map, name)),
+        None => {
+            print!("{}", overview(&map, name));
+            return 0;
+        }
+    };
+    match result {
+        Ok(()) => {
+            println!("Wrote {}", output.map_or("", String::as_str));
+            0
+        }
+        Err(e) => {
+            eprintln!(
+                "Couldn't write {}: {}",
+                output.map_or("", String::as_str),
+                e
+            );
+            1
+        }
+    }
+}
+
+/// The map at `path`, or the one built into the game, with the game's tile kinds.
+fn load(path: Option<&str>) -> Result<Map, String> {
+    let source = match path {
+        Some(path) => {
+            fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?
+        }
+        None => {
+            let (_, bytes) = EMBEDDED_ASSETS
+                .iter()
+                .find(|(path, _)| *path == "maps/start.map")
+                .expect("the start map is built in");
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+    };
+    let map = Map::parse(&source).map_err(|e| format!("Couldn't read the map: {}", e))?;
+    Ok(with_tile_kinds(map, &Settings::load(&Config::load())))
+}
+
+/// The map as text with its coordinates around it, the start marked, then every kind of
+/// tile on it.
+fn overview(map: &Map, name: &str) -> String {
+    let mut text = format!("{}, {}x{}", name, map.width(), map.height());
+    if let Some(par) = time_trial::par(map) {
+        write!(text, ", par {}s", par).unwrap();
+    }
+    text.push_str("\n\n    ");
+    for x in 0..map.width() {
+        text.push(std::char::from_digit(x as u32 % 10, 10).unwrap());
+    }
+    text.push('\n');
+    let spawn = (START.x as usize, START.y as usize);
+    for y in 0..map.height() {
+        write!(text, "{:>3} ", y).unwrap();
+        for x in 0..map.width() {
+            match (x, y) == spawn {
+                true => text.push(SPAWN),
+                false => text.push(map.get(x, y).unwrap_or(' ')),
+            }
+        }
+        text.push('\n');
+    }
+
+    text.push_str("\nLegend:\n");
+    writeln!(text, "  {}   start at {:?}", SPAWN, spawn).unwrap();
+    for feature in Feature::ALL.iter() {
+        let mut tiles = Vec::new();
+        let mut places = Vec::new();
+        for (x, y) in tiles_of(map) {
+            if Feature::of(map, x, y) == *feature {
+                let tile = map.get(x, y).unwrap_or(' ');
+                if !tiles.contains(&tile) {
+                    tiles.push(tile);
+                }
+                places.push((x, y));
+            }
+        }
+        if places.is_empty() {
+            continue;
+        }
+        let tiles: String = tiles.into_iter().collect();
+        let details = match feature.is_landmark() {
+            true => places
+                .iter()
+                .map(|(x, y)| match map.portal(*x, *y) {
+                    Some(to) => format!("{:?} to {:?}", (x, y), to),
+                    None => format!("{:?}", (x, y)),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            false => format!("{} tiles", places.len()),
+        };
+        writeln!(text, "  {:<4}{}: {}", tiles, feature.name(), details).unwrap();
+    }
+    text
+}
+
+/// How a map plays, from the start to the exit.
+pub struct Report {
+    width: usize,
+    height: usize,
+    /// Tiles that don't block the way.
+    walkable: usize,
+    /// Walkable tiles that can be reached from the start.
+    reachable: usize,
+    pickups: usize,
+    checkpoints: usize,
+    signs: usize,
+    /// The fewest steps from tile to tile, through portals, to any exit, if one can be
+    /// reached.
+    shortest_path: Option<u32>,
+    /// Tiles every way from the start to the exit goes through.
+    choke_points: Vec<(usize, usize)>,
+}
+
+impl Report {
+    pub fn of(map: &Map) -> Report {
+        let start = (START.x as usize, START.y as usize);
+        let count = |wanted: &[char]| {
+            tiles_of(map)
+                .filter(|(x, y)| map.get(*x, *y).is_some_and(|tile| wanted.contains(&tile)))
+                .count()
+        };
+        let exits: Vec<(usize, usize)> = tiles_of(map)
+            .filter(|(x, y)| map.get(*x, *y) == Some(EXIT))
+            .collect();
+        let to_exit = |steps: &[Option<u32>]| {
+            exits
+                .iter()
+                .filter_map(|(x, y)| steps[y * map.width() + x])
+                .min()
+        };
+        let steps = steps_from(map, start, None);
+        let shortest_path = to_exit(&steps);
+        let choke_points = match shortest_path {
+            Some(_) => tiles_of(map)
+                .filter(|tile| *tile != start && !exits.contains(tile))
+                .filter(|(x, y)| steps[y * map.width() + x].is_some())
+                .filter(|tile| to_exit(&steps_from(map, start, Some(*tile))).is_none())
+                .collect(),
+            None => Vec::new(),
+        };
+        Report {
+            width: map.width(),
+            height: map.height(),
+            walkable: tiles_of(map)
+                .filter(|(x, y)| is_walkable(map, *x, *y))
+                .count(),
+            reachable: steps.iter().flatten().count(),
+            pickups: count(&[SPEED_BOOST, NIGHT_VISION]),
+            checkpoints: count(&[CHECKPOINT]),
+            signs: count(&[SIGN]),
+            shortest_path,
+            choke_points,
+        }
+    }
+
+    fn to_text(&self, name: &str) -> String {
+        let area = self.width * self.height;
+        let mut text = format!("{}, {}x{}\n", name, self.width, self.height);
+        writeln!(
+            text,
+            "walkable area:  {} tiles ({}%), {} reachable from the start",
+            self.walkable,
+            self.walkable * 100 / area.max(1),
+            self.reachable
+        )
+        .unwrap();
+        writeln!(text, "pickups:        {}", self.pickups).unwrap();
+        writeln!(text, "checkpoints:    {}", self.checkpoints).unwrap();
+        writeln!(text, "signs:          {}", self.signs).unwrap();
+        match self.shortest_path {
+            Some(steps) => writeln!(text, "shortest path:  {} tiles to the exit", steps),
+            None => writeln!(text, "shortest path:  the exit can't be reached"),
+        }
+        .unwrap();
+        let choke_points: Vec<String> = self
+            .choke_points
+            .iter()
+            .map(|tile| format!("{:?}", tile))
+            .collect();
+        match choke_points.is_empty() {
+            true => text.push_str("choke points:   none\n"),
+            false => writeln!(text, "choke points:   {}", choke_points.join(", ")).unwrap(),
+        }
+        text
+    }
+}
+
+/// Whether a body can stand on the tile. Thin walls count as walls, since they can only be
+/// walked along.
+fn is_walkable(map: &Map, x: usize, y: usize) -> bool {
+    map.get(x, y).is_some_and(|tile| !map.is_solid(tile))
+}
+
+/// Fewest steps from `start` to every tile, row by row, going up, down, left or right. A
+/// step into a portal comes out of the other end. `blocked` is treated as a wall.
+fn steps_from(
+    map: &Map,
+    start: (usize, usize),
+    blocked: Option<(usize, usize)>,
+) -> Vec<Option<u32>> {
+    let width = map.width();
+    let mut steps = vec![None; width * map.height()];
+    if !is_walkable(map, start.0, start.1) {
+        return steps;
+    }
+    steps[start.1 * width + start.0] = Some(0);
+    let mut queue = VecDeque::from(vec![start]);
+    while let Some((x, y)) = queue.pop_front() {
+        let next = steps[y * width + x].unwrap_or_default() + 1;
+        let neighbors = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in neighbors.iter().copied() {
+            if !is_walkable(map, nx, ny) || Some((nx, ny)) == blocked {
+                continue;
+            }
+            let (nx, ny) = match map.portal(nx, ny) {
+                Some(exit) => {
+                    steps[ny * width + nx].get_or_insert(next);
+                    exit
+                }
+                None => (nx, ny),
+            };
+            if steps[ny * width + nx].is_none() && Some((nx, ny)) != blocked {
+                steps[ny * width + nx] = Some(next);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    steps
+}
+
+fn tiles_of(map: &Map) -> impl Iterator<Item = (usize, usize)> {
+    let width = map.width();
+    (0..map.height()).flat_map(move |y| (0..width).map(move |x| (x, y)))
+}
+
+#[cfg(feature = "png-screenshots")]
+mod raster {
+    use super::{tiles_of, Feature};
+    use crate::START;
+    use olc_fps::Map;
+    use std::{fs::File, io, io::BufWriter, path::Path};
+
+    /// Pixels per tile side.
+    const TILE_SIZE: usize = 16;
+    const SPAWN_COLOR: [u8; 3] = [230, 40, 40];
+
+    /// Each tile as a square colored after what it is, the start as a dot.
+    pub fn save_png(map: &Map, path: &Path) -> io::Result<()> {
+        let (width, height) = (map.width() * TILE_SIZE, map.height() * TILE_SIZE);
+        let mut pixels = vec![0; width * height * 3];
+        for (x, y) in tiles_of(map) {
+            let color = Feature::of(map, x, y).color();
+            for py in y * TILE_SIZE..(y + 1) * TILE_SIZE {
+                for px in x * TILE_SIZE..(x + 1) * TILE_SIZE {
+                    let i = (py * width + px) * 3;
+                    pixels[i..i + 3].copy_from_slice(&color);
+                }
+            }
+        }
+        let (cx, cy) = (START.x * TILE_SIZE as f32, START.y * TILE_SIZE as f32);
+        for py in 0..height {
+            for px in 0..width {
+                let (dx, dy) = (px as f32 + 0.5 - cx, py as f32 + 0.5 - cy);
+                if dx * dx + dy * dy <= (TILE_SIZE as f32 / 3.0).powi(2) {
+                    let i = (py * width + px) * 3;
+                    pixels[i..i + 3].copy_from_slice(&SPAWN_COLOR);
+                }
+            }
+        }
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .map_err(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PORTALS;
+
+    /// A 16x16 map of walls with `rows` drawn over it at their index, around the start at
+    /// (8, 8).
+    fn map(rows: &[(usize, &str)]) -> Map {
+        let mut source = String::new();
+        for y in 0..16 {
+            let row = rows
+                .iter()
+                .find(|(i, _)| *i == y)
+                .map_or("", |(_, row)| *row);
+            source.push_str(&format!("{:#<16}\n", row));
+        }
+        Map::parse(&source).unwrap().with_portals(&PORTALS)
+    }
+
+    #[test]
+    fn finds_the_only_way_through() {
+        let map = map(&[
+            (8, "########....####"),
+            (9, "###########.####"),
+            (10, "###########X####"),
+        ]);
+        let report = Report::of(&map);
+        assert_eq!(report.shortest_path, Some(5));
+        assert_eq!(report.choke_points, vec![(9, 8), (10, 8), (11, 8), (11, 9)]);
+    }
+
+    #[test]
+    fn goes_through_portals() {
+        let map = map(&[(8, "########.1######"), (14, "1X")]);
+        let report = Report::of(&map);
+        assert_eq!(report.shortest_path, Some(2));
+        assert_eq!(report.reachable, 4);
+        assert_eq!(report.choke_points, vec![(9, 8), (0, 14)]);
+    }
+}
@@ -0,0 +1,37 @@
+//! Vectors come from `glam`, plus the few angle helpers the games need.
+//!
+//! Angles are in radians and measured from the +y axis towards +x, so an angle of 0
+//! looks down the map and `direction(a)` is `(sin a, cos a)`.
+
+use std::f32::consts::{PI, TAU};
+
+pub use glam::Vec2;
+
+/// Wraps `angle` into `[-π, π)`.
+pub fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+/// The unit vector pointing along `angle`.
+pub fn direction(angle: f32) -> Vec2 {
+    Vec2::new(angle.sin(), angle.cos())
+}
+
+/// The angle `v` points along, the inverse of [`direction`].
+pub fn angle_of(v: Vec2) -> f32 {
+    v.x.atan2(v.y)
+}
+
+/// Moves `from` towards `to` by at most `max_step`, without overshooting.
+pub fn approach(from: f32, to: f32, max_step: f32) -> f32 {
+    from + (to - from).clamp(-max_step, max_step)
+}
+
+pub fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+/// Interpolates between two angles, turning the short way around.
+pub fn lerp_angle(from: f32, to: f32, t: f32) -> f32 {
+    from + wrap_angle(to - from) * t
+}
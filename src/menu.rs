@@ -0,0 +1,264 @@
+use crate::{controls::key_name, SCREEN_HEIGHT, SCREEN_WIDTH};
+use olc_fps::{input::Actions, keys, Align, Border, Keys, Locale, Palette, Screen};
+
+/// Moving around menus, bound to the arrow keys and WASD. A gamepad can drive them too
+/// through [`Actions::set_analog`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+pub fn default_actions() -> Actions<MenuAction> {
+    Actions::new()
+        .with_binding(MenuAction::Up, keys::UP)
+        .with_binding(MenuAction::Up, 'W' as i32)
+        .with_binding(MenuAction::Down, keys::DOWN)
+        .with_binding(MenuAction::Down, 'S' as i32)
+        .with_binding(MenuAction::Left, keys::LEFT)
+        .with_binding(MenuAction::Left, 'A' as i32)
+        .with_binding(MenuAction::Right, keys::RIGHT)
+        .with_binding(MenuAction::Right, 'D' as i32)
+        .with_binding(MenuAction::Select, keys::RETURN)
+        .with_binding(MenuAction::Select, keys::SPACE)
+        .with_binding(MenuAction::Back, keys::ESCAPE)
+}
+
+/// Letters and digits typed in, like a name for the leaderboard.
+pub struct TextInput {
+    pub text: String,
+    max_length: usize,
+}
+
+impl TextInput {
+    pub fn new(max_length: usize) -> Self {
+        TextInput {
+            text: String::new(),
+            max_length,
+        }
+    }
+
+    /// Types the letters and digits pressed, and erases the last one with backspace.
+    /// Returns true when ENTER confirms some text.
+    pub fn update(&mut self, keys: &Keys) -> bool {
+        for key in (b'A'..=b'Z').chain(b'0'..=b'9') {
+            if keys.pressed(key as i32) && self.text.len() < self.max_length {
+                self.text.push(key as char);
+            }
+        }
+        if keys.pressed(keys::BACK) {
+            self.text.pop();
+        }
+        keys.pressed(keys::RETURN) && !self.text.is_empty()
+    }
+}
+
+/// How an item of a menu is changed.
+pub enum Widget {
+    /// Only selected, for the menu's owner to act on.
+    Button,
+    /// Flipped by selecting it or with left and right.
+    Toggle(bool),
+    /// Stepped between `min` and `max` with left and right.
+    Slider {
+        value: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    },
+    /// One of `options`, keys into the `Locale`, cycled by selecting it or with left and
+    /// right.
+    Choice {
+        options: Vec<&'static str>,
+        selected: usize,
+    },
+    /// Once selected, typed into until ENTER or ESCAPE.
+    Text { input: TextInput, editing: bool },
+    /// Once selected, replaced with the next key pressed. ESCAPE cancels.
+    KeyCapture { keys: Vec<i32>, capturing: bool },
+}
+
+pub struct Item {
+    /// What the menu's owner knows the item by.
+    pub id: &'static str,
+    /// Key into the `Locale`.
+    pub label: &'static str,
+    pub widget: Widget,
+}
+
+impl Item {
+    pub fn new(id: &'static str, label: &'static str, widget: Widget) -> Self {
+        Item { id, label, widget }
+    }
+}
+
+/// What a menu update did, with the index of the item it concerns.
+pub enum Event {
+    Changed(usize),
+    /// A button was selected.
+    Selected(usize),
+    Back,
+}
+
+/// A list of items moved through with up and down, each changed through its widget.
+pub struct Menu {
+    /// Key into the `Locale`.
+    title: &'static str,
+    items: Vec<Item>,
+    selected: usize,
+    /// Shown instead of the hint, like what the last change did.
+    note: Option<String>,
+}
+
+impl Menu {
+    /// Panics if there are no `items`.
+    pub fn new(title: &'static str, items: Vec<Item>) -> Self {
+        assert!(!items.is_empty(), "a menu needs items");
+        Menu {
+            title,
+            items,
+            selected: 0,
+            note: None,
+        }
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn items_mut(&mut self) -> &mut [Item] {
+        &mut self.items
+    }
+
+    pub fn set_note(&mut self, note: Option<String>) {
+        self.note = note;
+    }
+
+    /// Moves around the menu with `actions`. A text field or a key capture that's in use
+    /// takes the raw `keys` instead, until it's done.
+    pub fn update(&mut self, actions: &mut Actions<MenuAction>, keys: &Keys) -> Option<Event> {
+        let index = self.selected;
+        match &mut self.items[index].widget {
+            Widget::Text { input, editing } if *editing => {
+                // Whatever gets typed is meant for the field, not for moving around
+                actions.clear_buffer();
+                if input.update(keys) || keys.pressed(keys::ESCAPE) {
+                    *editing = false;
+                    return Some(Event::Changed(index));
+                }
+                return None;
+            }
+            Widget::KeyCapture {
+                keys: bound,
+                capturing,
+            } if *capturing => {
+                actions.clear_buffer();
+                if keys.pressed(keys::ESCAPE) {
+                    *capturing = false;
+                    return None;
+                }
+                *bound = vec![(keys::BACK..256).find(|key| keys.pressed(*key))?];
+                *capturing = false;
+                return Some(Event::Changed(index));
+            }
+            _ => {}
+        }
+
+        if actions.take(MenuAction::Back) {
+            return Some(Event::Back);
+        }
+        let count = self.items.len();
+        if actions.take(MenuAction::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if actions.take(MenuAction::Down) {
+            self.selected = (self.selected + 1) % count;
+        }
+        let right = actions.take(MenuAction::Right) as i32;
+        let step = right - actions.take(MenuAction::Left) as i32;
+        let select = actions.take(MenuAction::Select);
+        let index = self.selected;
+        match &mut self.items[index].widget {
+            Widget::Button if select => return Some(Event::Selected(index)),
+            Widget::Toggle(on) if select || step != 0 => *on = !*on,
+            Widget::Slider {
+                value,
+                min,
+                max,
+                step: size,
+            } if step != 0 => *value = (*value + step as f32 * *size).clamp(*min, *max),
+            Widget::Choice { options, selected } if select || step != 0 => {
+                let step = if step == 0 { 1 } else { step };
+                *selected = (*selected as i32 + step).rem_euclid(options.len() as i32) as usize;
+            }
+            Widget::Text { editing, .. } if select => {
+                *editing = true;
+                return None;
+            }
+            Widget::KeyCapture { capturing, .. } if select => {
+                *capturing = true;
+                return None;
+            }
+            _ => return None,
+        }
+        Some(Event::Changed(index))
+    }
+
+    /// Fills the screen with the menu in a frame, the selected item highlighted.
+    pub fn draw(&self, screen: &mut Screen, locale: &Locale, palette: &Palette) {
+        screen.clear(' ', palette.background);
+        let (width, height) = (60, self.items.len() as i32 + 7);
+        let left = (SCREEN_WIDTH as i32 - width) / 2;
+        let top = (SCREEN_HEIGHT as i32 - height) / 2;
+        screen.frame(left, top, width, height, &Border::DOUBLE, palette.frame);
+        screen.centered_string(top + 1, locale.get(self.title), palette.highlight);
+        for (i, item) in self.items.iter().enumerate() {
+            let y = top + 3 + i as i32;
+            let (marker, color) = match i == self.selected {
+                true => ('>', palette.highlight),
+                false => (' ', palette.text),
+            };
+            let label = format!("{} {}", marker, locale.get(item.label));
+            screen.string(left + 2, y, &label, color);
+            let value = value_text(&item.widget, locale);
+            screen.aligned_string(left + 3, y, width - 6, &value, Align::Right, color);
+        }
+        let selected = &self.items[self.selected];
+        let hint = match (&selected.widget, &self.note) {
+            (
+                Widget::KeyCapture {
+                    capturing: true, ..
+                },
+                _,
+            ) => locale.format("menu.press_key", &[("action", &locale.get(selected.label))]),
+            (_, Some(note)) => note.clone(),
+            _ => locale.get("menu.hint").to_string(),
+        };
+        screen.centered_string(top + height - 2, &hint, palette.text);
+    }
+}
+
+/// What's shown on the right of an item.
+fn value_text(widget: &Widget, locale: &Locale) -> String {
+    match widget {
+        Widget::Button => String::new(),
+        Widget::Toggle(on) => locale.get(if *on { "menu.on" } else { "menu.off" }).into(),
+        Widget::Slider { value, .. } => format!("< {:.0} >", value),
+        Widget::Choice { options, selected } => format!("< {} >", locale.get(options[*selected])),
+        Widget::Text { input, editing } => match editing {
+            true => format!("{}_", input.text),
+            false => input.text.clone(),
+        },
+        Widget::KeyCapture {
+            capturing: true, ..
+        } => "...".to_string(),
+        Widget::KeyCapture { keys, .. } => {
+            let names: Vec<String> = keys.iter().copied().map(key_name).collect();
+            names.join(", ")
+        }
+    }
+}
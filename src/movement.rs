@@ -0,0 +1,141 @@
+use olc_fps::math::{approach, Vec2};
+
+/// Floor tile that keeps the player sliding.
+pub const ICE: char = '~';
+/// Water shallow enough to wade through.
+pub const WATER: char = 'w';
+/// Water that closes over the player's head, who can only stay under while air lasts.
+pub const DEEP_WATER: char = 'W';
+
+/// How moving feels on a kind of floor.
+#[derive(Clone, Copy, Debug)]
+pub struct Surface {
+    /// Tiles per second gained each second while moving.
+    pub acceleration: f32,
+    /// Rate at which speed is lost, 10 stops the player within a tenth of a second.
+    pub friction: f32,
+    /// Tiles per second that moving can reach.
+    pub max_speed: f32,
+}
+
+impl Surface {
+    pub const NORMAL: Surface = Surface {
+        acceleration: 60.0,
+        friction: 10.0,
+        max_speed: 5.0,
+    };
+    pub const ICE: Surface = Surface {
+        acceleration: 6.0,
+        friction: 0.5,
+        max_speed: 6.0,
+    };
+    pub const WATER: Surface = Surface {
+        acceleration: 15.0,
+        friction: 4.0,
+        max_speed: 2.5,
+    };
+
+    /// The same surface, with moving `factor` times as fast.
+    pub fn with_speed(self, factor: f32) -> Surface {
+        Surface {
+            max_speed: self.max_speed * factor,
+            ..self
+        }
+    }
+}
+
+/// Slows `velocity` down with friction, then speeds it up along `wish`, whose length is
+/// how much of the top speed is wanted. Only the speed along `wish` is capped, so turning
+/// while moving can carry a bit more speed than the cap.
+pub fn accelerate(velocity: Vec2, wish: Vec2, surface: &Surface, delta_time: f32) -> Vec2 {
+    let velocity = velocity * (1.0 - surface.friction * delta_time).max(0.0);
+    let wanted = wish.length().min(1.0) * surface.max_speed;
+    let direction = match wish.try_normalize() {
+        Some(direction) => direction,
+        None => return velocity,
+    };
+    let missing = wanted - velocity.dot(direction);
+    let gain = (surface.acceleration * delta_time).min(missing.max(0.0));
+    velocity + direction * gain
+}
+
+/// How much faster sprinting is than walking.
+pub const SPRINT_FACTOR: f32 = 1.6;
+/// How much slower moving is while crouched.
+pub const CROUCH_FACTOR: f32 = 0.5;
+/// How far the horizon rises when fully crouched, as a fraction of the view height.
+pub const CROUCH_PITCH: f32 = -0.12;
+/// Crouches per second, standing up takes as long as getting down.
+const CROUCH_RATE: f32 = 6.0;
+/// Seconds of sprinting a full stamina bar lasts.
+const SPRINT_DURATION: f32 = 4.0;
+/// Seconds it takes to get the full bar back.
+const RECOVERY_DURATION: f32 = 6.0;
+/// Seconds of air when diving in with a full breath.
+const AIR_DURATION: f32 = 10.0;
+/// How much the view rises and falls while in water, as a fraction of the view height.
+const BOB_PITCH: f32 = 0.03;
+/// Radians per second of the bobbing, about one rise and fall every two seconds.
+const BOB_RATE: f32 = 3.0;
+/// Stamina needed to sprint again after running out.
+const RECOVERED: f32 = 0.3;
+
+/// Eases how far down the player is, from 0 standing to 1 crouched.
+pub fn crouch(current: f32, crouching: bool, delta_time: f32) -> f32 {
+    let target = if crouching { 1.0 } else { 0.0 };
+    approach(current, target, CROUCH_RATE * delta_time)
+}
+
+/// What limits sprinting.
+#[derive(Clone, Copy, Debug)]
+pub struct Stamina {
+    /// From 0 when spent to 1.
+    pub level: f32,
+    /// Set when running out, until enough has come back.
+    exhausted: bool,
+}
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Stamina {
+            level: 1.0,
+            exhausted: false,
+        }
+    }
+}
+
+impl Stamina {
+    /// Spends stamina while `wanted`, or gets some back. Returns whether the player gets
+    /// to sprint this tick.
+    pub fn update(&mut self, wanted: bool, delta_time: f32) -> bool {
+        if self.exhausted && self.level >= RECOVERED {
+            self.exhausted = false;
+        }
+        let sprinting = wanted && !self.exhausted;
+        if sprinting {
+            self.level = (self.level - delta_time / SPRINT_DURATION).max(0.0);
+            self.exhausted = self.level == 0.0;
+        } else {
+            self.level = (self.level + delta_time / RECOVERY_DURATION).min(1.0);
+        }
+        sprinting
+    }
+
+    /// Whether sprinting has to wait for stamina to come back.
+    pub fn exhausted(&self) -> bool {
+        self.exhausted
+    }
+}
+
+/// How far the view is shifted `seconds` into bobbing in water.
+pub fn bob(seconds: f32) -> f32 {
+    BOB_PITCH * (seconds * BOB_RATE).sin()
+}
+
+/// Uses up air while `submerged` and breathes it back in otherwise, from 0 to 1.
+pub fn breathe(air: f32, submerged: bool, delta_time: f32) -> f32 {
+    match submerged {
+        true => (air - delta_time / AIR_DURATION).max(0.0),
+        false => (air + delta_time / AIR_DURATION * 4.0).min(1.0),
+    }
+}
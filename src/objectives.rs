@@ -0,0 +1,201 @@
+use olc_fps::{Locale, Palette, Screen};
+
+/// Tile character the player has to reach to finish the level.
+pub const EXIT: char = 'X';
+
+#[derive(PartialEq)]
+pub enum Goal {
+    ReadSign {
+        x: usize,
+        y: usize,
+    },
+    /// Reaching the exit only counts once every other required objective is done.
+    ReachExit {
+        time_limit: Option<f32>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Status {
+    Pending,
+    Complete,
+    Failed,
+}
+
+pub struct Objective {
+    /// Key into the `Locale` the objectives are drawn with.
+    pub description: &'static str,
+    pub goal: Goal,
+    pub required: bool,
+    pub status: Status,
+}
+
+impl Objective {
+    pub fn new(description: &'static str, goal: Goal, required: bool) -> Self {
+        Objective {
+            description,
+            goal,
+            required,
+            status: Status::Pending,
+        }
+    }
+
+    /// Reads an objective from a map's `@objective` line: `read_sign` with the sign's column
+    /// and row, or `reach_exit` with an optional time limit in seconds, then `optional` for
+    /// one the level can be finished without, like `@objective reach_exit 120`.
+    pub fn parse(value: &str) -> Option<Objective> {
+        let mut words: Vec<&str> = value.split_whitespace().collect();
+        let required = words.last() != Some(&"optional");
+        if !required {
+            words.pop();
+        }
+        let (description, goal) = match words.as_slice() {
+            ["read_sign", x, y] => {
+                let (x, y) = (x.parse().ok()?, y.parse().ok()?);
+                ("objectives.read_sign", Goal::ReadSign { x, y })
+            }
+            ["reach_exit"] => (
+                "objectives.reach_exit",
+                Goal::ReachExit { time_limit: None },
+            ),
+            ["reach_exit", limit] => {
+                let limit = limit
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|l| *l > 0.0 && l.is_finite())?;
+                let time_limit = Some(limit);
+                ("objectives.reach_exit", Goal::ReachExit { time_limit })
+            }
+            _ => return None,
+        };
+        Some(Objective::new(description, goal, required))
+    }
+}
+
+/// Things happening in the level that objectives may care about.
+pub enum Event {
+    SignRead { x: usize, y: usize },
+    ExitReached,
+}
+
+pub struct Objectives {
+    list: Vec<Objective>,
+    elapsed: f32,
+}
+
+impl Objectives {
+    pub fn new(list: Vec<Objective>) -> Self {
+        Objectives { list, elapsed: 0.0 }
+    }
+
+    /// Swaps in the objectives of a changed map, keeping the progress made on those it
+    /// still has.
+    pub fn replace(&mut self, mut list: Vec<Objective>) {
+        for objective in list.iter_mut() {
+            if let Some(old) = self.list.iter().find(|old| old.goal == objective.goal) {
+                objective.status = old.status;
+            }
+        }
+        self.list = list;
+    }
+
+    /// Seconds the level has been played for.
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.elapsed += delta_time;
+        let elapsed = self.elapsed;
+        for objective in self.list.iter_mut() {
+            if let Goal::ReachExit {
+                time_limit: Some(limit),
+            } = objective.goal
+            {
+                if objective.status == Status::Pending && elapsed > limit {
+                    objective.status = Status::Failed;
+                }
+            }
+        }
+    }
+
+    pub fn handle(&mut self, event: Event) {
+        let exit_open = self.exit_open();
+        for objective in self.list.iter_mut() {
+            if objective.status != Status::Pending {
+                continue;
+            }
+            let done = match (&objective.goal, &event) {
+                (Goal::ReadSign { x, y }, Event::SignRead { x: sx, y: sy }) => x == sx && y == sy,
+                (Goal::ReachExit { .. }, Event::ExitReached) => exit_open,
+                _ => false,
+            };
+            if done {
+                objective.status = Status::Complete;
+            }
+        }
+    }
+
+    /// The exit stays locked until every required objective besides reaching it is complete.
+    pub fn exit_open(&self) -> bool {
+        self.list
+            .iter()
+            .filter(|o| o.required && !matches!(o.goal, Goal::ReachExit { .. }))
+            .all(|o| o.status == Status::Complete)
+    }
+
+    /// A required objective that failed, which means starting the level over.
+    pub fn failed(&self) -> Option<&Objective> {
+        self.list
+            .iter()
+            .find(|o| o.required && o.status == Status::Failed)
+    }
+
+    pub fn level_complete(&self) -> bool {
+        self.list
+            .iter()
+            .filter(|o| o.required)
+            .all(|o| o.status == Status::Complete)
+    }
+
+    /// Lists the objectives from row `top` down.
+    pub fn draw(&self, screen: &mut Screen, top: usize, locale: &Locale, palette: &Palette) {
+        let mut lines: Vec<String> = self
+            .list
+            .iter()
+            .map(|objective| {
+                let mark = match objective.status {
+                    Status::Pending => ' ',
+                    Status::Complete => 'x',
+                    Status::Failed => '!',
+                };
+                let mut line = format!("[{}] {}", mark, locale.get(objective.description));
+                if let Goal::ReachExit {
+                    time_limit: Some(limit),
+                } = objective.goal
+                {
+                    if objective.status == Status::Pending {
+                        let left = (limit - self.elapsed).max(0.0) as u32;
+                        let time = format!("{}:{:02}", left / 60, left % 60);
+                        line.push(' ');
+                        line.push_str(&locale.format("objectives.time_left", &[("time", &time)]));
+                    }
+                }
+                if !objective.required {
+                    line.push(' ');
+                    line.push_str(locale.get("objectives.optional"));
+                }
+                line
+            })
+            .collect();
+        if self.level_complete() {
+            lines.push(locale.get("objectives.level_complete").to_string());
+        } else if self.exit_open() {
+            lines.push(locale.get("objectives.exit_open").to_string());
+        }
+
+        for (row, line) in lines.iter().enumerate() {
+            screen.string(0, (top + row) as i32, line, palette.text);
+        }
+    }
+}
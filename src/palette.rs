@@ -0,0 +1,112 @@
+//! Sets of colors for each part of the screen, so the whole look can be swapped at once.
+
+use crate::draw::color::*;
+
+/// The color attribute of everything the games draw, by what it's used for.
+pub struct Palette {
+    /// Key into the `Locale`.
+    pub name: &'static str,
+    /// Empty cells, the ceiling and anything covering the view like letterbox bars.
+    pub background: u16,
+    /// Walls from the closest to the farthest shade.
+    pub walls: [u16; 4],
+    pub floor: u16,
+    pub water: u16,
+    /// Stars in the night sky.
+    pub sky: u16,
+    /// Regular text, like objectives and dialogue.
+    pub text: u16,
+    /// Text that should stand out, like titles and notices. Foreground only, since it's
+    /// also drawn over the spaces between letters.
+    pub highlight: u16,
+    /// Borders around boxes and framed views.
+    pub frame: u16,
+    pub map_wall: u16,
+    pub map_floor: u16,
+    pub map_player: u16,
+    /// Other markers on the map, like the spectator camera.
+    pub map_marker: u16,
+}
+
+impl Palette {
+    /// Grey shades with a few colored accents, the game's original look.
+    pub const STANDARD: Palette = Palette {
+        name: "palette.standard",
+        background: DEFAULT,
+        walls: [FG_GREY; 4],
+        floor: FG_GREY,
+        water: FG_BLUE,
+        sky: FG_WHITE,
+        text: FG_WHITE,
+        highlight: FG_CYAN,
+        frame: FG_GREY,
+        map_wall: FG_GREY,
+        map_floor: FG_DARK_GREY,
+        map_player: FG_YELLOW,
+        map_marker: FG_CYAN,
+    };
+
+    /// Blues and yellows only, which stay apart for red-green color blindness.
+    pub const DEUTERANOPIA: Palette = Palette {
+        name: "palette.deuteranopia",
+        background: DEFAULT,
+        walls: [FG_WHITE, FG_GREY, FG_BLUE, FG_DARK_BLUE],
+        floor: FG_DARK_YELLOW,
+        water: FG_BLUE,
+        sky: FG_YELLOW,
+        text: FG_WHITE,
+        highlight: FG_YELLOW,
+        frame: FG_BLUE,
+        map_wall: FG_BLUE,
+        map_floor: FG_DARK_GREY,
+        map_player: FG_YELLOW,
+        map_marker: FG_WHITE,
+    };
+
+    /// Bright white on black, with markers on solid backgrounds.
+    pub const HIGH_CONTRAST: Palette = Palette {
+        name: "palette.high_contrast",
+        background: FG_WHITE | BG_BLACK,
+        walls: [FG_WHITE; 4],
+        floor: FG_WHITE,
+        water: FG_CYAN,
+        sky: FG_WHITE,
+        text: FG_WHITE,
+        highlight: FG_YELLOW,
+        frame: FG_WHITE,
+        map_wall: FG_WHITE,
+        map_floor: FG_GREY,
+        map_player: FG_BLACK | BG_YELLOW,
+        map_marker: FG_BLACK | BG_WHITE,
+    };
+
+    /// Everything in shades of green, like an old monochrome monitor.
+    pub const RETRO: Palette = Palette {
+        name: "palette.retro",
+        background: FG_GREEN | BG_BLACK,
+        walls: [FG_GREEN, FG_GREEN, FG_DARK_GREEN, FG_DARK_GREEN],
+        floor: FG_DARK_GREEN,
+        water: FG_GREEN,
+        sky: FG_GREEN,
+        text: FG_DARK_GREEN,
+        highlight: FG_GREEN,
+        frame: FG_DARK_GREEN,
+        map_wall: FG_GREEN,
+        map_floor: FG_DARK_GREEN,
+        map_player: FG_BLACK | BG_GREEN,
+        map_marker: FG_BLACK | BG_DARK_GREEN,
+    };
+
+    /// How the palette is named in the config file, like `high_contrast`.
+    pub fn id(&self) -> &'static str {
+        self.name.trim_start_matches("palette.")
+    }
+
+    /// Every built-in palette, in the order they're cycled through.
+    pub const ALL: [&'static Palette; 4] = [
+        &Palette::STANDARD,
+        &Palette::DEUTERANOPIA,
+        &Palette::HIGH_CONTRAST,
+        &Palette::RETRO,
+    ];
+}
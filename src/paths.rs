@@ -0,0 +1,82 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Where the config file goes: `%APPDATA%\olc_fps` on Windows, `$XDG_CONFIG_HOME/olc_fps`
+/// or `~/.config/olc_fps` elsewhere, and the working directory when none of them is set.
+pub fn config_dir() -> PathBuf {
+    let base = match cfg!(windows) {
+        true => var("APPDATA"),
+        false => var("XDG_CONFIG_HOME").or_else(|| Some(var("HOME")?.join(".config"))),
+    };
+    base.map_or_else(PathBuf::new, |base| base.join("olc_fps"))
+}
+
+/// Where saved progress, screenshots and recordings go: `%APPDATA%\olc_fps` on Windows,
+/// `$XDG_DATA_HOME/olc_fps` or `~/.local/share/olc_fps` elsewhere.
+pub fn data_dir() -> PathBuf {
+    let base = match cfg!(windows) {
+        true => var("APPDATA"),
+        false => var("XDG_DATA_HOME").or_else(|| Some(var("HOME")?.join(".local/share"))),
+    };
+    base.map_or_else(PathBuf::new, |base| base.join("olc_fps"))
+}
+
+/// Where the log goes, since nothing is lost if it's cleared: `%LOCALAPPDATA%\olc_fps` on
+/// Windows, `$XDG_CACHE_HOME/olc_fps` or `~/.cache/olc_fps` elsewhere.
+pub fn cache_dir() -> PathBuf {
+    let base = match cfg!(windows) {
+        true => var("LOCALAPPDATA"),
+        false => var("XDG_CACHE_HOME").or_else(|| Some(var("HOME")?.join(".cache"))),
+    };
+    base.map_or_else(PathBuf::new, |base| base.join("olc_fps"))
+}
+
+/// Writes `contents` to `path` so that a crash or a power cut halfway through leaves the old
+/// file or the new one, never a mix of both. They're written to a temporary file first,
+/// flushed to the disk, which then takes the old one's place. The old one is kept as a
+/// backup, see [`read`].
+pub fn write(path: &Path, contents: &str) -> io::Result<()> {
+    let temp = with_suffix(path, ".tmp");
+    let mut file = File::create(&temp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    if path.exists() {
+        fs::copy(path, with_suffix(path, ".bak"))?;
+    }
+    fs::rename(&temp, path)?;
+    // The rename only sticks once the directory listing it is flushed too
+    #[cfg(unix)]
+    {
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        File::open(dir.unwrap_or_else(|| Path::new(".")))?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// The contents of a file saved with [`write`], or of its backup if the file is missing or
+/// can't be read. Empty if neither can.
+pub fn read(path: &Path) -> String {
+    fs::read_to_string(path)
+        .or_else(|_| fs::read_to_string(with_suffix(path, ".bak")))
+        .unwrap_or_default()
+}
+
+/// `path` with `suffix` added after its extension, like `stats.txt.bak`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// An environment variable holding a path, unless it's empty.
+fn var(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
@@ -0,0 +1,78 @@
+/// Picked up by walking over it, the tile turns into floor.
+pub const SPEED_BOOST: char = '>';
+pub const NIGHT_VISION: char = 'o';
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Powerup {
+    /// Moving faster.
+    SpeedBoost,
+    /// Seeing in the dark as if it was broad daylight.
+    NightVision,
+}
+
+impl Powerup {
+    pub const ALL: [Powerup; 2] = [Powerup::SpeedBoost, Powerup::NightVision];
+
+    /// The powerup lying on `tile`, if any.
+    pub fn on(tile: char) -> Option<Powerup> {
+        match tile {
+            SPEED_BOOST => Some(Powerup::SpeedBoost),
+            NIGHT_VISION => Some(Powerup::NightVision),
+            _ => None,
+        }
+    }
+
+    /// Seconds it lasts.
+    pub fn duration(self) -> f32 {
+        match self {
+            Powerup::SpeedBoost => 15.0,
+            Powerup::NightVision => 30.0,
+        }
+    }
+
+    /// Key into the `Locale`, shown on the HUD.
+    pub fn name(self) -> &'static str {
+        match self {
+            Powerup::SpeedBoost => "hud.speed_boost",
+            Powerup::NightVision => "hud.night_vision",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How much faster a speed boost moves the player.
+pub const SPEED_BOOST_FACTOR: f32 = 1.5;
+
+/// The powerups in effect and how long each has left. Picking one up again starts its
+/// time over rather than adding to it.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Effects {
+    remaining: [f32; Powerup::ALL.len()],
+}
+
+impl Effects {
+    pub fn add(&mut self, powerup: Powerup) {
+        self.remaining[powerup.index()] = powerup.duration();
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        for remaining in &mut self.remaining {
+            *remaining = (*remaining - delta_time).max(0.0);
+        }
+    }
+
+    pub fn active(&self, powerup: Powerup) -> bool {
+        self.remaining[powerup.index()] > 0.0
+    }
+
+    /// Powerups in effect with the seconds they have left.
+    pub fn iter(&self) -> impl Iterator<Item = (Powerup, f32)> + '_ {
+        Powerup::ALL
+            .iter()
+            .map(move |p| (*p, self.remaining[p.index()]))
+            .filter(|(_, remaining)| *remaining > 0.0)
+    }
+}
@@ -0,0 +1,368 @@
+use crate::{
+    map::{Map, Segment},
+    math::Vec2,
+};
+
+/// The face of a tile a ray went through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    /// The face at the tile's lowest x.
+    West,
+    /// The face at the tile's highest x.
+    East,
+    /// The face at the tile's lowest y.
+    North,
+    /// The face at the tile's highest y.
+    South,
+}
+
+/// Where a ray stopped.
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    /// From the origin to `hit_point`, in map units.
+    pub distance: f32,
+    pub tile: char,
+    pub tile_coords: (usize, usize),
+    pub side: Side,
+    pub hit_point: Vec2,
+    /// Where the ray would have started to reach `hit_point` in a straight line. That's
+    /// the origin it was cast from unless it went through portals or off mirrors.
+    pub origin: Vec2,
+    /// How many mirrors the ray bounced off before getting here.
+    pub reflections: u32,
+}
+
+/// Walks the grid from `origin` along `dir` and returns the first solid tile it enters,
+/// or `None` if nothing solid is within `max_distance`.
+///
+/// Rays leaving the map stop there without a hit. A ray starting inside a solid tile
+/// ignores it and only reports the next one, unless that tile is only a [`Segment`] the
+/// ray runs into. `dir` doesn't need to be normalized.
+///
+/// This is a DDA traversal, so every tile on the way is visited exactly once and the
+/// distance is exact instead of being found by small steps. Tiles limited to a segment
+/// are only hit where the ray crosses it, and let it through otherwise. Rays going into a
+/// portal carry on from the other end, with the distance still counted from `origin`
+/// while the hit point is where the ray ended up.
+///
+/// ```
+/// use olc_fps::{math::Vec2, raycast, Map, Side};
+///
+/// let map = Map::new(4, 1, "#..#".chars().collect());
+/// let hit = raycast(&map, Vec2::new(1.5, 0.5), Vec2::new(1.0, 0.0), 10.0).unwrap();
+/// assert_eq!(hit.tile_coords, (3, 0));
+/// assert_eq!(hit.side, Side::West);
+/// assert_eq!(hit.distance, 1.5);
+/// ```
+pub fn raycast(map: &Map, origin: Vec2, dir: Vec2, max_distance: f32) -> Option<RayHit> {
+    ray_hits(map, origin, dir, max_distance).next()
+}
+
+/// Like [`raycast`], but keeps going through solid tiles and yields every one entered,
+/// nearest first. Renderers use it to see through tiles like bars and windows.
+///
+/// Rays bounce off mirrors, as many in a row as the map allows, and stop at the next one.
+/// Distances after a bounce keep counting from `origin`.
+///
+/// ```
+/// use olc_fps::{math::Vec2, raycast::ray_hits, Map};
+///
+/// let map = Map::new(5, 1, "#.|.#".chars().collect()).with_solid(&['#', '|']);
+/// let hits: Vec<char> = ray_hits(&map, Vec2::new(1.5, 0.5), Vec2::new(1.0, 0.0), 10.0)
+///     .map(|hit| hit.tile)
+///     .collect();
+/// assert_eq!(hits, ['|', '#']);
+/// ```
+pub fn ray_hits(map: &Map, origin: Vec2, dir: Vec2, max_distance: f32) -> RayHits<'_> {
+    let (dir, done) = match dir.try_normalize() {
+        Some(dir) => (dir, false),
+        None => (Vec2::ZERO, true),
+    };
+    let mut hits = RayHits {
+        map,
+        origin,
+        dir,
+        max_distance,
+        done,
+        start: !done,
+        reflections: 0,
+        tile: (origin.x.floor() as i64, origin.y.floor() as i64),
+        step: (1, 1),
+        delta: Vec2::ZERO,
+        next: Vec2::ZERO,
+    };
+    hits.aim(origin, dir);
+    hits
+}
+
+/// The solid tiles along a ray, see [`ray_hits`].
+pub struct RayHits<'a> {
+    map: &'a Map,
+    /// Moved along with the ray whenever it goes through a portal.
+    origin: Vec2,
+    dir: Vec2,
+    max_distance: f32,
+    /// Set once the ray has left the map or gone too far.
+    done: bool,
+    /// Set until the tile the ray starts in has been checked for a segment.
+    start: bool,
+    reflections: u32,
+    tile: (i64, i64),
+    step: (i64, i64),
+    delta: Vec2,
+    /// Distance along the ray to the next grid line on each axis.
+    next: Vec2,
+}
+
+impl Iterator for RayHits<'_> {
+    type Item = RayHit;
+
+    fn next(&mut self) -> Option<RayHit> {
+        if self.start {
+            self.start = false;
+            let hit = self.segment_hit(self.tile, 0.0);
+            if hit.is_some() {
+                return hit;
+            }
+        }
+        while !self.done {
+            let (distance, side) = if self.next.x < self.next.y {
+                self.tile.0 += self.step.0;
+                self.next.x += self.delta.x;
+                let side = if self.step.0 > 0 {
+                    Side::West
+                } else {
+                    Side::East
+                };
+                (self.next.x - self.delta.x, side)
+            } else {
+                self.tile.1 += self.step.1;
+                self.next.y += self.delta.y;
+                let side = if self.step.1 > 0 {
+                    Side::North
+                } else {
+                    Side::South
+                };
+                (self.next.y - self.delta.y, side)
+            };
+            if let Some((to_x, to_y)) = self.portal() {
+                let (to_x, to_y) = (to_x as i64, to_y as i64);
+                let (x, y) = self.tile;
+                self.origin += Vec2::new((to_x - x) as f32, (to_y - y) as f32);
+                self.tile = (to_x, to_y);
+            }
+            let (x, y) = self.tile;
+            let tile = if distance > self.max_distance || x < 0 || y < 0 {
+                None
+            } else {
+                self.map.get(x as usize, y as usize)
+            };
+            match tile {
+                None => self.done = true,
+                Some(tile) if self.map.segment(tile).is_some() => {
+                    let hit = self.segment_hit(self.tile, distance);
+                    if hit.is_some() {
+                        return hit;
+                    }
+                }
+                Some(tile) if self.map.is_solid(tile) => {
+                    let hit = RayHit {
+                        distance,
+                        tile,
+                        tile_coords: (x as usize, y as usize),
+                        side,
+                        hit_point: self.origin + self.dir * distance,
+                        origin: self.origin,
+                        reflections: self.reflections,
+                    };
+                    if self.map.is_mirror(tile) {
+                        self.reflect(&hit);
+                    }
+                    return Some(hit);
+                }
+                Some(_) => {}
+            }
+        }
+        None
+    }
+}
+
+impl RayHits<'_> {
+    /// Points the ray along `dir` from `origin`, which has to be on the line through the
+    /// current tile.
+    fn aim(&mut self, origin: Vec2, dir: Vec2) {
+        let (x, y) = self.tile;
+        // Distance along the ray to cross a whole tile, and to reach the next grid line
+        let delta = Vec2::new((1.0 / dir.x).abs(), (1.0 / dir.y).abs());
+        let first = |origin: f32, tile: i64, dir: f32, delta: f32| match dir {
+            d if d < 0.0 => (origin - tile as f32) * delta,
+            d if d > 0.0 => (tile as f32 + 1.0 - origin) * delta,
+            _ => f32::INFINITY,
+        };
+        self.origin = origin;
+        self.dir = dir;
+        self.step = (
+            if dir.x < 0.0 { -1 } else { 1 },
+            if dir.y < 0.0 { -1 } else { 1 },
+        );
+        self.delta = delta;
+        self.next = Vec2::new(
+            first(origin.x, x, dir.x, delta.x),
+            first(origin.y, y, dir.y, delta.y),
+        );
+    }
+
+    /// Bounces the ray back off the mirror it just hit, or stops it there once it's
+    /// bounced as many times as the map allows.
+    fn reflect(&mut self, hit: &RayHit) {
+        if self.reflections >= self.map.max_reflections() {
+            self.done = true;
+            return;
+        }
+        self.reflections += 1;
+        let dir = match hit.side {
+            Side::West | Side::East => {
+                self.tile.0 -= self.step.0;
+                Vec2::new(-self.dir.x, self.dir.y)
+            }
+            Side::North | Side::South => {
+                self.tile.1 -= self.step.1;
+                Vec2::new(self.dir.x, -self.dir.y)
+            }
+        };
+        self.aim(hit.hit_point - dir * hit.distance, dir);
+    }
+
+    /// Where the portal in the current tile leads.
+    fn portal(&self) -> Option<(usize, usize)> {
+        let (x, y) = self.tile;
+        if x < 0 || y < 0 {
+            return None;
+        }
+        self.map.portal(x as usize, y as usize)
+    }
+
+    /// Where the ray crosses the segment of the tile it's in, if it does so between
+    /// `enter`, the distance it came into the tile at, and the next grid line.
+    fn segment_hit(&self, (x, y): (i64, i64), enter: f32) -> Option<RayHit> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        let tile = self.map.get(x as usize, y as usize)?;
+        let Segment { from, to } = self.map.segment(tile)?;
+        let corner = Vec2::new(x as f32, y as f32);
+        let along = to - from;
+        let denominator = self.dir.perp_dot(along);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let offset = corner + from - self.origin;
+        let distance = offset.perp_dot(along) / denominator;
+        let t = offset.perp_dot(self.dir) / denominator;
+        let exit = self.next.x.min(self.next.y);
+        if !(0.0..=1.0).contains(&t) || distance < enter || distance > exit.min(self.max_distance) {
+            return None;
+        }
+        // Name the face after the axis its normal points the most along
+        let mut normal = along.perp();
+        if normal.dot(self.dir) > 0.0 {
+            normal = -normal;
+        }
+        let side = match normal {
+            n if n.x.abs() >= n.y.abs() && n.x < 0.0 => Side::West,
+            n if n.x.abs() >= n.y.abs() => Side::East,
+            n if n.y < 0.0 => Side::North,
+            _ => Side::South,
+        };
+        Some(RayHit {
+            distance,
+            tile,
+            tile_coords: (x as usize, y as usize),
+            side,
+            hit_point: self.origin + self.dir * distance,
+            origin: self.origin,
+            reflections: self.reflections,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{floor_point, random_map, Rng};
+
+    /// Far enough to cross any of the maps tested.
+    const MAX_DISTANCE: f32 = 100.0;
+
+    /// Whether a wall is in the way from `from` to `to`, and how sure it is: how far the
+    /// wall is from `to` along the ray.
+    fn blocked(map: &Map, from: Vec2, to: Vec2) -> (bool, f32) {
+        let distance = from.distance(to);
+        match raycast(map, from, to - from, MAX_DISTANCE) {
+            Some(hit) => (hit.distance < distance, (hit.distance - distance).abs()),
+            None => (false, f32::INFINITY),
+        }
+    }
+
+    /// Walks the ray in small steps until it's in a wall.
+    fn brute_force(map: &Map, origin: Vec2, dir: Vec2, step: f32) -> Option<f32> {
+        let mut distance = 0.0;
+        while distance < MAX_DISTANCE {
+            let point = origin + dir * distance;
+            if map.is_wall(point.x, point.y) {
+                return Some(distance);
+            }
+            distance += step;
+        }
+        None
+    }
+
+    #[test]
+    fn line_of_sight_goes_both_ways() {
+        let mut rng = Rng::new(0x1d5);
+        for case in 0..500 {
+            let map = random_map(&mut rng, 16, 16, 0.2);
+            let (a, b) = match (floor_point(&mut rng, &map), floor_point(&mut rng, &map)) {
+                (Some(a), Some(b)) if a.distance(b) > 1e-3 => (a, b),
+                _ => continue,
+            };
+            let (a_to_b, a_margin) = blocked(&map, a, b);
+            let (b_to_a, b_margin) = blocked(&map, b, a);
+            // A wall touching the end point could go either way with rounding
+            if a_margin.min(b_margin) < 1e-3 {
+                continue;
+            }
+            assert_eq!(a_to_b, b_to_a, "case {}: {:?} and {:?}", case, a, b);
+        }
+    }
+
+    #[test]
+    fn hits_where_small_steps_do() {
+        const STEP: f32 = 1e-3;
+        let mut rng = Rng::new(0xdda);
+        for case in 0..300 {
+            let map = random_map(&mut rng, 16, 16, 0.2);
+            let origin = match floor_point(&mut rng, &map) {
+                Some(origin) => origin,
+                None => continue,
+            };
+            let dir = rng.direction();
+            let hit = raycast(&map, origin, dir, MAX_DISTANCE).map(|hit| hit.distance);
+            let stepped = brute_force(&map, origin, dir, STEP);
+            let (hit, stepped) = match (hit, stepped) {
+                (Some(hit), Some(stepped)) => (hit, stepped),
+                _ => panic!("case {}: {:?} against {:?}", case, hit, stepped),
+            };
+            // Steps stop up to one step past the wall, never before it
+            assert!(
+                (hit..=hit + STEP * 2.0).contains(&stepped),
+                "case {}: {} against {} from {:?} along {:?}",
+                case,
+                hit,
+                stepped,
+                origin,
+                dir
+            );
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{screenshot, SCREEN_HEIGHT, SCREEN_SIZE, SCREEN_WIDTH};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Frames per second kept in a recording, no matter how fast the game renders.
+const CAPTURE_RATE: f32 = 15.0;
+/// Two minutes of frames, to keep memory bounded if recording is left on.
+const MAX_FRAMES: usize = 15 * 120;
+
+/// Frames captured since recording was turned on, each with its time in seconds.
+pub struct Recording {
+    started: Instant,
+    frames: Vec<(f32, Vec<u16>)>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Recording {
+            started: Instant::now(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Keeps a copy of the screen if enough time passed since the last frame.
+    /// Returns false once the recording is full.
+    pub fn capture(&mut self, screen: &[u16]) -> bool {
+        if self.frames.len() >= MAX_FRAMES {
+            return false;
+        }
+        let time = self.started.elapsed().as_secs_f32();
+        let due = match self.frames.last() {
+            Some((last, _)) => time - last >= 1.0 / CAPTURE_RATE,
+            None => true,
+        };
+        if due {
+            self.frames.push((time, screen[..SCREEN_SIZE].to_vec()));
+        }
+        true
+    }
+
+    /// Writes the recording as an asciinema cast in `dir` and returns its path. With the
+    /// `gif-recordings` feature an animated GIF is written alongside it.
+    pub fn save(&self, dir: &Path) -> io::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = dir.join(format!("recording-{}.cast", timestamp));
+        self.save_cast(&path, timestamp)?;
+        #[cfg(feature = "gif-recordings")]
+        self.save_gif(&path.with_extension("gif"))?;
+        Ok(path)
+    }
+
+    /// See <https://docs.asciinema.org/manual/asciicast/v2/> for the format.
+    fn save_cast(&self, path: &Path, timestamp: u64) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(
+            file,
+            r#"{{"version": 2, "width": {}, "height": {}, "timestamp": {}}}"#,
+            SCREEN_WIDTH, SCREEN_HEIGHT, timestamp
+        )?;
+        for (time, screen) in &self.frames {
+            // Redraw the whole frame from the top left corner
+            let output = format!(
+                "\x1b[H{}",
+                screenshot::to_text(screen).replace('\n', "\r\n")
+            );
+            writeln!(file, "[{:.3}, \"o\", \"{}\"]", time, escape_json(&output))?;
+        }
+        file.flush()
+    }
+
+    #[cfg(feature = "gif-recordings")]
+    fn save_gif(&self, path: &Path) -> io::Result<()> {
+        use screenshot::raster::{rasterize, IMAGE_HEIGHT, IMAGE_WIDTH};
+
+        let file = BufWriter::new(File::create(path)?);
+        let palette = [0x00, 0x00, 0x00, 0xff, 0xff, 0xff];
+        let mut encoder =
+            gif::Encoder::new(file, IMAGE_WIDTH as u16, IMAGE_HEIGHT as u16, &palette)
+                .map_err(io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(io::Error::other)?;
+        for (i, (time, screen)) in self.frames.iter().enumerate() {
+            let next = self.frames.get(i + 1).map_or(time + 1.0, |(t, _)| *t);
+            let pixels: Vec<u8> = rasterize(screen).iter().map(|p| p / 0xff).collect();
+            let mut frame = gif::Frame::from_indexed_pixels(
+                IMAGE_WIDTH as u16,
+                IMAGE_HEIGHT as u16,
+                pixels,
+                None,
+            );
+            // GIF delays are in hundredths of a second
+            frame.delay = ((next - time) * 100.0).round() as u16;
+            encoder.write_frame(&frame).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
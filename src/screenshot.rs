@@ -0,0 +1,92 @@
+use crate::{SCREEN_HEIGHT, SCREEN_SIZE, SCREEN_WIDTH};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Converts the screen buffer to UTF-8 text, one line per row.
+pub fn to_text(screen: &[u16]) -> String {
+    let mut text = String::with_capacity(SCREEN_SIZE + SCREEN_HEIGHT);
+    for row in screen[..SCREEN_SIZE].chunks(SCREEN_WIDTH) {
+        let line: String = std::char::decode_utf16(row.iter().copied())
+            .map(|c| match c {
+                Ok('\0') | Err(_) => ' ',
+                Ok(c) => c,
+            })
+            .collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Saves the current frame in `dir` and returns the path of the text file. With the
+/// `png-screenshots` feature a PNG is written alongside it.
+pub fn save(dir: &Path, screen: &[u16]) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = dir.join(format!("screenshot-{}.txt", timestamp));
+    fs::write(&path, to_text(screen))?;
+    #[cfg(feature = "png-screenshots")]
+    raster::save_png(screen, &path.with_extension("png"))?;
+    Ok(path)
+}
+
+#[cfg(any(feature = "png-screenshots", feature = "gif-recordings"))]
+pub mod raster {
+    use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+    use font8x8::{UnicodeFonts, BASIC_FONTS, BLOCK_FONTS, BOX_FONTS};
+
+    /// Console cells are about twice as tall as they are wide, so every font row is doubled.
+    pub const CELL_WIDTH: usize = 8;
+    pub const CELL_HEIGHT: usize = 16;
+    pub const IMAGE_WIDTH: usize = SCREEN_WIDTH * CELL_WIDTH;
+    pub const IMAGE_HEIGHT: usize = SCREEN_HEIGHT * CELL_HEIGHT;
+
+    fn glyph(c: char) -> [u8; 8] {
+        BASIC_FONTS
+            .get(c)
+            .or_else(|| BLOCK_FONTS.get(c))
+            .or_else(|| BOX_FONTS.get(c))
+            .unwrap_or([0; 8])
+    }
+
+    /// Draws the screen buffer with the embedded bitmap font, one grayscale byte per pixel.
+    pub fn rasterize(screen: &[u16]) -> Vec<u8> {
+        let mut pixels = vec![0; IMAGE_WIDTH * IMAGE_HEIGHT];
+        for cy in 0..SCREEN_HEIGHT {
+            for cx in 0..SCREEN_WIDTH {
+                let c = std::char::from_u32(screen[cy * SCREEN_WIDTH + cx] as u32).unwrap_or(' ');
+                for (row, bits) in glyph(c).iter().enumerate() {
+                    for col in 0..CELL_WIDTH {
+                        if bits & (1 << col) == 0 {
+                            continue;
+                        }
+                        let x = cx * CELL_WIDTH + col;
+                        for y in [cy * CELL_HEIGHT + row * 2, cy * CELL_HEIGHT + row * 2 + 1] {
+                            pixels[y * IMAGE_WIDTH + x] = 0xff;
+                        }
+                    }
+                }
+            }
+        }
+        pixels
+    }
+
+    #[cfg(feature = "png-screenshots")]
+    pub fn save_png(screen: &[u16], path: &std::path::Path) -> std::io::Result<()> {
+        use std::{fs::File, io, io::BufWriter};
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, IMAGE_WIDTH as u32, IMAGE_HEIGHT as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&rasterize(screen)))
+            .map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,401 @@
+use crate::{movement::Surface, paths, weather, FOV};
+use olc_fps::{locale::StringTable, Palette};
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+use tracing::level_filters::LevelFilter;
+
+/// Every setting, with its default as written in a fresh config file and what it's for.
+const OPTIONS: [(&str, &str, &str); 20] = [
+    (
+        "lang",
+        "",
+        "Language like fr or fr_CA, the system's when empty",
+    ),
+    (
+        "reduced_motion",
+        "0",
+        "1 for still pictures instead of animations",
+    ),
+    ("turn_speed", "215", "Degrees turned per second, up to 1440"),
+    ("turn_ramp", "0", "Seconds for turning to reach full speed"),
+    (
+        "snap_turn",
+        "0",
+        "Degrees turned on each press instead of smoothly",
+    ),
+    ("fov", "45", "Field of view in degrees, from 30 to 150"),
+    ("log", "warn", "off, error, warn, info, debug or trace"),
+    (
+        "title_status",
+        "0",
+        "1 to show the status in the window title",
+    ),
+    (
+        "acceleration",
+        "60",
+        "Speed gained per second while walking",
+    ),
+    ("friction", "10", "How quickly walking comes to a stop"),
+    (
+        "max_speed",
+        "5",
+        "Walking speed in tiles per second, up to 50",
+    ),
+    (
+        "mirror_bounces",
+        "2",
+        "Mirrors a view can bounce off in a row",
+    ),
+    ("weather", "rain", "rain, snow or none"),
+    (
+        "palette",
+        "standard",
+        "standard, deuteranopia, high_contrast or retro",
+    ),
+    (
+        "day_length",
+        "300",
+        "Seconds from one morning to the next, 0 to stop time",
+    ),
+    ("time_trial", "0", "1 to play against the clock"),
+    (
+        "cheats",
+        "0",
+        "1 to accept typed cheat codes, never in time trials",
+    ),
+    (
+        "random_start",
+        "0",
+        "1 to start on a random spot of the map",
+    ),
+    (
+        "data_dir",
+        "",
+        "Where saves and the log go, like . for a portable install",
+    ),
+    ("name", "", "Filled in for new records, letters and digits"),
+];
+
+/// Where the config file is.
+pub fn path() -> PathBuf {
+    paths::config_dir().join("config.txt")
+}
+
+/// Where a setting was set, each one overriding the ones before it.
+#[derive(Clone, Copy, Debug)]
+pub enum Layer {
+    File,
+    Environment,
+    CommandLine,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Layer::File => "config file",
+            Layer::Environment => "environment",
+            Layer::CommandLine => "command line",
+        })
+    }
+}
+
+/// Settings as they were set, on top of the built-in defaults: `key = value` lines of the
+/// config file, then `OLC_FPS_KEY` environment variables, then `--key=value` arguments.
+/// Dashes in arguments stand for underscores, and an argument without a value means `1`.
+#[derive(Default)]
+pub struct Config {
+    file: StringTable,
+    args: Vec<String>,
+}
+
+impl Config {
+    /// A config file that can't be read is replaced by its backup, if any, and lines that
+    /// aren't `key = value` are skipped.
+    pub fn load() -> Config {
+        let source = paths::read(&path());
+        let lines: Vec<&str> = source.lines().filter(|line| line.contains('=')).collect();
+        Config {
+            file: StringTable::parse(&lines.join("\n")).unwrap_or_default(),
+            args: env::args().skip(1).collect(),
+        }
+    }
+
+    /// The value of `key` from the last layer that sets it.
+    pub fn get(&self, key: &str) -> Option<(String, Layer)> {
+        let flag = format!("--{}", key.replace('_', "-"));
+        let arg = self
+            .args
+            .iter()
+            .rev()
+            .find_map(|arg| match arg.strip_prefix(&flag)? {
+                "" => Some("1".to_string()),
+                value => value.strip_prefix('=').map(str::to_string),
+            });
+        let var = || env::var(format!("OLC_FPS_{}", key.to_uppercase())).ok();
+        arg.map(|value| (value, Layer::CommandLine))
+            .or_else(|| var().map(|value| (value, Layer::Environment)))
+            .or_else(|| Some((self.file.get(key)?.to_string(), Layer::File)))
+    }
+
+    fn value(&self, key: &str) -> Option<String> {
+        self.get(key).map(|(value, _)| value)
+    }
+
+    /// Set to anything but `0`, `false` or nothing.
+    fn flag(&self, key: &str) -> bool {
+        self.value(key)
+            .is_some_and(|value| !matches!(value.trim(), "" | "0" | "false"))
+    }
+
+    /// Parsed as a number, leaving out NaN and the infinities.
+    fn number(&self, key: &str) -> Option<f32> {
+        let number: f32 = self.value(key)?.trim().parse().ok()?;
+        Some(number).filter(|n| n.is_finite())
+    }
+}
+
+/// Runs `config show`, which lists every setting with where it was set, or
+/// `config write-default`, which writes a config file with every setting commented out.
+/// Returns the exit code.
+pub fn command(args: &[String]) -> i32 {
+    let path = path();
+    match args.first().map(String::as_str) {
+        Some("show") => {
+            let config = Config::load();
+            println!("# {}", path.display());
+            for (key, default, _) in OPTIONS.iter() {
+                match config.get(key) {
+                    Some((value, layer)) => println!("{} = {}  ({})", key, value, layer),
+                    None => println!("{} = {}  (default)", key, default),
+                }
+            }
+            0
+        }
+        Some("write-default") if path.exists() => {
+            eprintln!("{} already exists, leaving it as it is", path.display());
+            1
+        }
+        Some("write-default") => match write_default(&path) {
+            Ok(()) => {
+                println!("Wrote {}", path.display());
+                0
+            }
+            Err(e) => {
+                eprintln!("Couldn't write {}: {}", path.display(), e);
+                1
+            }
+        },
+        _ => {
+            eprintln!("Usage: olc_fps config show | olc_fps config write-default");
+            2
+        }
+    }
+}
+
+/// Sets `key` in the config file, on its line or the commented out one `config
+/// write-default` left, or on a new one. Environment variables and arguments still
+/// override it.
+pub fn write_option(key: &str, value: &str) -> io::Result<()> {
+    let path = path();
+    let source = paths::read(&path);
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let line = format!("{} = {}", key, value);
+    let sets_key = |line: &String| match line.trim_start_matches('#').split_once('=') {
+        Some((k, _)) => k.trim() == key,
+        None => false,
+    };
+    match lines.iter().position(sets_key) {
+        Some(i) => lines[i] = line,
+        None => lines.push(line),
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    paths::write(&path, &(lines.join("\n") + "\n"))
+}
+
+/// Commented out, so the defaults can still change with the game until a line is edited.
+fn write_default(path: &Path) -> io::Result<()> {
+    let mut contents = String::from(
+        "# olc_fps settings, uncomment a line to change it. Environment variables\n\
+         # (OLC_FPS_FOV) and arguments (--fov=90) override this file.\n",
+    );
+    for (key, default, description) in OPTIONS.iter() {
+        contents.push_str(&format!("\n# {}\n# {} = {}\n", description, key, default));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    paths::write(path, &contents)
+}
+
+/// How fast turning gets while a turn key is held.
+#[derive(Clone, Copy)]
+pub enum TurnCurve {
+    /// Full speed right away.
+    Constant,
+    /// Starts slow for precise aiming and reaches full speed after this many seconds.
+    Ramp(f32),
+}
+
+impl TurnCurve {
+    /// Fraction of the full turn speed after holding a turn key for `held` seconds.
+    pub fn factor(self, held: f32) -> f32 {
+        match self {
+            TurnCurve::Constant => 1.0,
+            TurnCurve::Ramp(seconds) if seconds > 0.0 => (0.3 + 0.7 * held / seconds).min(1.0),
+            TurnCurve::Ramp(_) => 1.0,
+        }
+    }
+}
+
+/// Player preferences, read once at startup from a [`Config`].
+pub struct Settings {
+    /// Like `fr` or `fr_CA`, empty for English.
+    pub language: String,
+    /// Replaces camera pans, sweeping animations and blinking with still pictures.
+    pub reduced_motion: bool,
+    /// Radians per second at full speed.
+    pub turn_speed: f32,
+    pub turn_curve: TurnCurve,
+    /// Turns by this many radians on each key press instead of smoothly.
+    pub snap_turn: Option<f32>,
+    /// Horizontal field of view of the player's camera, in radians.
+    pub fov: f32,
+    /// The most detailed events written to the log file.
+    pub log_level: LevelFilter,
+    /// Keeps the FPS, map and mode in the window title.
+    pub title_status: bool,
+    /// How moving feels on ordinary floor.
+    pub ground: Surface,
+    /// How many mirrors in a row rays bounce off, each one costs another trace.
+    pub mirror_bounces: u32,
+    /// What falls on outdoor tiles, if anything.
+    pub weather: Option<weather::Kind>,
+    /// The colors everything is drawn with, menus included.
+    pub palette: &'static Palette,
+    /// Seconds a whole day and night take, `None` to stay in the morning.
+    pub day_length: Option<f32>,
+    /// Plays against the clock, with a timer, splits and medals.
+    pub time_trial: bool,
+    /// Typed cheat codes are accepted, always off in time trials.
+    pub cheats: bool,
+    /// Starts the player on a random spot of the map, for practice.
+    pub random_start: bool,
+    /// Where stats, best times, medals, screenshots and recordings are saved.
+    pub data_dir: PathBuf,
+    /// Where the log is written.
+    pub cache_dir: PathBuf,
+    /// Filled in for new records.
+    pub name: String,
+}
+
+impl Settings {
+    /// Reads the settings listed in `OPTIONS`, with the system's `LANG` when `lang` isn't
+    /// set. Angles are in degrees, durations in seconds and distances in tiles.
+    pub fn load(config: &Config) -> Self {
+        let number = |key| config.number(key);
+        let flag = |key| config.flag(key);
+        let language = config
+            .value("lang")
+            .or_else(|| env::var("LANG").ok())
+            .unwrap_or_default();
+        // `LANG` looks like `fr_CA.UTF-8`
+        let language = language.split('.').next().unwrap_or_default().to_string();
+        // Overrides both, to keep everything in one place
+        let data_dir = config
+            .value("data_dir")
+            .filter(|dir| !dir.trim().is_empty())
+            .map(PathBuf::from);
+        let turn_curve = match number("turn_ramp") {
+            Some(seconds) if seconds > 0.0 => TurnCurve::Ramp(seconds),
+            _ => TurnCurve::Constant,
+        };
+        Settings {
+            language,
+            reduced_motion: flag("reduced_motion"),
+            turn_speed: number("turn_speed")
+                .map_or(3.75, |degrees| degrees.clamp(1.0, 1440.0).to_radians()),
+            turn_curve,
+            snap_turn: number("snap_turn")
+                .filter(|degrees| *degrees > 0.0)
+                .map(|degrees| degrees.min(180.0).to_radians()),
+            fov: number("fov").map_or(FOV, |degrees| degrees.clamp(30.0, 150.0).to_radians()),
+            log_level: config
+                .value("log")
+                .and_then(|level| level.trim().parse().ok())
+                .unwrap_or(LevelFilter::WARN),
+            title_status: flag("title_status"),
+            ground: Surface {
+                acceleration: number("acceleration")
+                    .map_or(Surface::NORMAL.acceleration, |value| {
+                        value.clamp(0.0, 1000.0)
+                    }),
+                friction: number("friction")
+                    .map_or(Surface::NORMAL.friction, |value| value.clamp(0.0, 1000.0)),
+                max_speed: number("max_speed")
+                    .map_or(Surface::NORMAL.max_speed, |value| value.clamp(0.0, 50.0)),
+            },
+            weather: match config.value("weather").unwrap_or_default().trim() {
+                "snow" => Some(weather::Kind::Snow),
+                "none" | "off" => None,
+                _ => Some(weather::Kind::Rain),
+            },
+            palette: config
+                .value("palette")
+                .and_then(|id| Palette::ALL.iter().find(|p| p.id() == id.trim()))
+                .copied()
+                .unwrap_or(&Palette::STANDARD),
+            day_length: number("day_length")
+                .map_or(Some(300.0), |seconds| Some(seconds).filter(|s| *s > 0.0)),
+            mirror_bounces: number("mirror_bounces")
+                .map_or(2, |bounces| bounces.clamp(0.0, 8.0) as u32),
+            time_trial: flag("time_trial"),
+            cheats: flag("cheats") && !flag("time_trial"),
+            random_start: flag("random_start"),
+            cache_dir: data_dir.clone().unwrap_or_else(paths::cache_dir),
+            data_dir: data_dir.unwrap_or_else(paths::data_dir),
+            name: config.value("name").unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(args: &[&str]) -> Settings {
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        let config = Config {
+            file: StringTable::default(),
+            args,
+        };
+        Settings::load(&config)
+    }
+
+    #[test]
+    fn numbers_stay_in_range() {
+        let defaults = settings(&[]);
+        let broken = settings(&["--fov=nan", "--turn-speed=inf", "--max-speed=-inf"]);
+        assert_eq!(broken.fov, defaults.fov);
+        assert_eq!(broken.turn_speed, defaults.turn_speed);
+        assert_eq!(broken.ground.max_speed, defaults.ground.max_speed);
+
+        let extreme = settings(&[
+            "--fov=1e30",
+            "--turn-speed=-90",
+            "--max-speed=1e30",
+            "--acceleration=1e30",
+            "--friction=-1",
+            "--snap-turn=720",
+        ]);
+        assert_eq!(extreme.fov, 150f32.to_radians());
+        assert_eq!(extreme.turn_speed, 1f32.to_radians());
+        assert_eq!(extreme.ground.max_speed, 50.0);
+        assert_eq!(extreme.ground.acceleration, 1000.0);
+        assert_eq!(extreme.ground.friction, 0.0);
+        assert_eq!(extreme.snap_turn, Some(180f32.to_radians()));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{paths, SCREEN_HEIGHT, SCREEN_WIDTH};
+use olc_fps::{locale::StringTable, Align, Border, Locale, Palette, Screen};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Of the stats file format, files without a `version` are version 1 too. Bump it when
+/// a stat changes meaning, and convert the old one in [`migrate`].
+const VERSION: u32 = 1;
+
+/// Where lifetime stats are kept between runs.
+pub fn path(data_dir: &Path) -> PathBuf {
+    data_dir.join("lifetime-stats.txt")
+}
+
+/// Totals kept across every game ever played.
+#[derive(Default)]
+pub struct Stats {
+    pub games_started: u32,
+    pub levels_completed: u32,
+    /// Seconds spent in game, not counting menus.
+    pub play_time: f32,
+    /// In map tiles.
+    pub distance_walked: f32,
+}
+
+impl Stats {
+    pub fn load(path: &Path) -> Stats {
+        Stats::parse(&paths::read(path))
+    }
+
+    /// Missing or unreadable values start from zero, so a broken file never stops the game.
+    /// Neither do negative or infinite ones, which would stay wrong forever. So does every
+    /// stat of a file [`migrate`] can't bring up to the current version.
+    pub fn parse(source: &str) -> Stats {
+        let table = StringTable::parse(source).unwrap_or_default();
+        let version = match table.get("version") {
+            Some(version) => version.parse().ok(),
+            None => Some(1),
+        };
+        let table = match version.and_then(|version| migrate(version, table)) {
+            Some(table) => table,
+            None => return Stats::default(),
+        };
+        let number = |key| {
+            let value = table.get(key).and_then(|value| value.parse::<f32>().ok());
+            value.filter(|value| value.is_finite() && *value >= 0.0)
+        };
+        Stats {
+            games_started: number("games_started").unwrap_or(0.0) as u32,
+            levels_completed: number("levels_completed").unwrap_or(0.0) as u32,
+            play_time: number("play_time").unwrap_or(0.0),
+            distance_walked: number("distance_walked").unwrap_or(0.0),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = format!(
+            concat!(
+                "version = {}\ngames_started = {}\nlevels_completed = {}\n",
+                "play_time = {}\ndistance_walked = {}\n",
+            ),
+            VERSION,
+            self.games_started,
+            self.levels_completed,
+            self.play_time,
+            self.distance_walked
+        );
+        paths::write(path, &contents)
+    }
+
+    /// Fills the screen with a table of every stat.
+    pub fn draw(&self, screen: &mut Screen, locale: &Locale, palette: &Palette) {
+        screen.clear(' ', palette.background);
+        let seconds = self.play_time as u32;
+        let play_time = format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        let rows = [
+            ("stats.games_started", self.games_started.to_string()),
+            ("stats.levels_completed", self.levels_completed.to_string()),
+            ("stats.play_time", play_time),
+            (
+                "stats.distance_walked",
+                format!("{:.0}", self.distance_walked),
+            ),
+        ];
+
+        let (width, height) = (50, rows.len() as i32 + 6);
+        let left = (SCREEN_WIDTH as i32 - width) / 2;
+        let top = (SCREEN_HEIGHT as i32 - height) / 2;
+        screen.frame(left, top, width, height, &Border::DOUBLE, palette.frame);
+        screen.centered_string(top + 1, locale.get("stats.title"), palette.highlight);
+        for (i, (key, value)) in rows.iter().enumerate() {
+            let y = top + 3 + i as i32;
+            screen.string(left + 3, y, locale.get(key), palette.text);
+            screen.aligned_string(left + 3, y, width - 6, value, Align::Right, palette.text);
+        }
+        screen.centered_string(top + height - 2, locale.get("stats.back"), palette.text);
+    }
+}
+
+/// Brings the stats read from a `version` file up to the current [`VERSION`], or gives
+/// `None` for a version it doesn't know, like one written by a newer build whose stats
+/// might mean something else.
+fn migrate(version: u32, table: StringTable) -> Option<StringTable> {
+    match version {
+        VERSION => Some(table),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(source: &str) -> (u32, u32, f32, f32) {
+        let stats = Stats::parse(source);
+        let (started, completed) = (stats.games_started, stats.levels_completed);
+        (started, completed, stats.play_time, stats.distance_walked)
+    }
+
+    #[test]
+    fn files_without_a_version_are_version_1() {
+        let source = "games_started = 3\nlevels_completed = 1\nplay_time = 60.5\n";
+        assert_eq!(stats(source), (3, 1, 60.5, 0.0));
+        assert_eq!(
+            stats(&format!("version = 1\n{}", source)),
+            (3, 1, 60.5, 0.0)
+        );
+    }
+
+    #[test]
+    fn unknown_versions_start_from_zero() {
+        for version in ["2", "0", "one"] {
+            let source = format!("version = {}\ngames_started = 3\n", version);
+            assert_eq!(stats(&source), (0, 0, 0.0, 0.0), "version {}", version);
+        }
+    }
+}
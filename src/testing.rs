@@ -0,0 +1,72 @@
+//! Random maps and positions for tests that check a property over many cases.
+
+use crate::{map::Map, math::Vec2};
+
+/// A xorshift generator, seeded so failures can be replayed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift never leaves 0
+        Rng(seed.max(1))
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// From 0 to 1, 1 excluded.
+    pub fn unit(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.unit() * (max - min)
+    }
+
+    pub fn below(&mut self, max: usize) -> usize {
+        (self.next() % max as u64) as usize
+    }
+
+    /// A direction of length 1.
+    pub fn direction(&mut self) -> Vec2 {
+        let angle = self.range(0.0, std::f32::consts::TAU);
+        Vec2::new(angle.cos(), angle.sin())
+    }
+}
+
+/// A `width` by `height` map walled all around, with about `walls` of the tiles inside
+/// being walls too.
+pub fn random_map(rng: &mut Rng, width: usize, height: usize, walls: f32) -> Map {
+    let mut tiles = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            tiles.push(if border || rng.unit() < walls {
+                '#'
+            } else {
+                '.'
+            });
+        }
+    }
+    Map::new(width, height, tiles)
+}
+
+/// A point inside a random floor tile, `None` if there isn't any.
+pub fn floor_point(rng: &mut Rng, map: &Map) -> Option<Vec2> {
+    let floor: Vec<(usize, usize)> = (0..map.height())
+        .flat_map(|y| (0..map.width()).map(move |x| (x, y)))
+        .filter(|(x, y)| map.get(*x, *y) == Some('.'))
+        .collect();
+    if floor.is_empty() {
+        return None;
+    }
+    let (x, y) = floor[rng.below(floor.len())];
+    Some(Vec2::new(
+        x as f32 + rng.range(0.01, 0.99),
+        y as f32 + rng.range(0.01, 0.99),
+    ))
+}
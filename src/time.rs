@@ -0,0 +1,182 @@
+//! The game clock, and timers that run on it.
+
+/// How much of each new frame goes into the smoothed delta, lower is smoother.
+const SMOOTHING: f32 = 0.1;
+
+/// Scaled and real time since the game started, updated once per frame.
+///
+/// Gameplay should move by [`Time::delta`] so it follows pauses and the timescale,
+/// while things like UI animations can keep using [`Time::real_delta`].
+pub struct Time {
+    total: f32,
+    real_total: f32,
+    delta: f32,
+    real_delta: f32,
+    smoothed_delta: f32,
+    timescale: f32,
+    paused: bool,
+    ticks: u64,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Time {
+            total: 0.0,
+            real_total: 0.0,
+            delta: 0.0,
+            real_delta: 0.0,
+            smoothed_delta: 0.0,
+            timescale: 1.0,
+            paused: false,
+            ticks: 0,
+        }
+    }
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advances the clock by `real_delta` seconds of wall time.
+    pub fn update(&mut self, real_delta: f32) {
+        self.real_delta = real_delta;
+        self.real_total += real_delta;
+        self.smoothed_delta = if self.smoothed_delta > 0.0 {
+            self.smoothed_delta + (real_delta - self.smoothed_delta) * SMOOTHING
+        } else {
+            real_delta
+        };
+        self.delta = if self.paused {
+            0.0
+        } else {
+            real_delta * self.timescale
+        };
+        self.total += self.delta;
+        if !self.paused {
+            self.ticks += 1;
+        }
+    }
+
+    /// Advances game time by exactly `delta` seconds this frame, even while paused.
+    /// Meant for stepping through a paused game one tick at a time.
+    pub fn step(&mut self, delta: f32) {
+        self.delta = delta;
+        self.total += delta;
+        self.ticks += 1;
+    }
+
+    /// Seconds of game time this frame, zero while paused.
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    /// Seconds of wall time this frame, ignoring pauses and the timescale.
+    pub fn real_delta(&self) -> f32 {
+        self.real_delta
+    }
+
+    /// The real delta averaged over the last few frames, steadier for displaying.
+    pub fn smoothed_delta(&self) -> f32 {
+        self.smoothed_delta
+    }
+
+    /// Seconds of game time since the start.
+    pub fn total(&self) -> f32 {
+        self.total
+    }
+
+    /// Seconds of wall time since the start.
+    pub fn real_total(&self) -> f32 {
+        self.real_total
+    }
+
+    /// How many frames game time moved forward on, counting steps.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn timescale(&self) -> f32 {
+        self.timescale
+    }
+
+    /// Below 1 is slow motion, above is fast forward. Negative values are clamped to 0.
+    pub fn set_timescale(&mut self, timescale: f32) {
+        self.timescale = timescale.max(0.0);
+    }
+}
+
+/// Counts down a duration, once or over and over.
+#[derive(Clone, Debug)]
+pub struct Timer {
+    duration: f32,
+    elapsed: f32,
+    repeating: bool,
+}
+
+impl Timer {
+    /// Finishes once after `duration` seconds.
+    pub fn once(duration: f32) -> Self {
+        Timer {
+            duration,
+            elapsed: 0.0,
+            repeating: false,
+        }
+    }
+
+    /// Fires every `duration` seconds.
+    pub fn repeating(duration: f32) -> Self {
+        Timer {
+            repeating: true,
+            ..Timer::once(duration)
+        }
+    }
+
+    /// Advances the timer and returns how many times it went off, at most once for
+    /// one-shot timers. Pass [`Time::delta`] for gameplay or [`Time::real_delta`] for UI.
+    pub fn tick(&mut self, delta: f32) -> u32 {
+        if !self.repeating {
+            let was_finished = self.finished();
+            self.elapsed = (self.elapsed + delta).min(self.duration);
+            return (!was_finished && self.finished()) as u32;
+        }
+        if self.duration <= 0.0 {
+            return 1;
+        }
+        self.elapsed += delta;
+        let fired = (self.elapsed / self.duration) as u32;
+        self.elapsed %= self.duration;
+        fired
+    }
+
+    /// Always false for repeating timers.
+    pub fn finished(&self) -> bool {
+        !self.repeating && self.elapsed >= self.duration
+    }
+
+    /// Seconds until the timer next goes off.
+    pub fn remaining(&self) -> f32 {
+        (self.duration - self.elapsed).max(0.0)
+    }
+
+    /// How far along the current countdown is, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0)
+        } else {
+            1.0
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
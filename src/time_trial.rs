@@ -0,0 +1,176 @@
+use crate::{figlet::Font, leaderboard::format_time, paths, SCREEN_WIDTH};
+use olc_fps::{Border, Locale, Map, Palette, Screen};
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Floor tile that records a split the first time the player crosses it in a time trial.
+pub const CHECKPOINT: char = '+';
+
+/// How many splits are listed under the timer, the latest ones.
+const SHOWN_SPLITS: usize = 3;
+
+/// Of the medals file format, written on its first line. Bump it when the lines change
+/// meaning, and convert the old ones in [`Medals::parse`].
+const VERSION: u32 = 1;
+
+/// Where the best medal of each map is kept between runs.
+pub fn path(data_dir: &Path) -> PathBuf {
+    data_dir.join("medals.txt")
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl Medal {
+    /// Gold for finishing within `par` seconds, silver within a quarter more and bronze
+    /// within half more.
+    pub fn earned(time: f32, par: f32) -> Option<Medal> {
+        match time / par {
+            ratio if ratio <= 1.0 => Some(Medal::Gold),
+            ratio if ratio <= 1.25 => Some(Medal::Silver),
+            ratio if ratio <= 1.5 => Some(Medal::Bronze),
+            _ => None,
+        }
+    }
+
+    /// Key into the `Locale`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Medal::Bronze => "medal.bronze",
+            Medal::Silver => "medal.silver",
+            Medal::Gold => "medal.gold",
+        }
+    }
+
+    fn parse(text: &str) -> Option<Medal> {
+        match text {
+            "bronze" => Some(Medal::Bronze),
+            "silver" => Some(Medal::Silver),
+            "gold" => Some(Medal::Gold),
+            _ => None,
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Medal::Bronze => "bronze",
+            Medal::Silver => "silver",
+            Medal::Gold => "gold",
+        }
+    }
+}
+
+/// The par time set by the map's `@par` line, in seconds.
+pub fn par(map: &Map) -> Option<f32> {
+    map.property("par")?.parse().ok().filter(|par| *par > 0.0)
+}
+
+/// The best medal won on every map, by map hash like the leaderboard.
+#[derive(Default)]
+pub struct Medals {
+    maps: HashMap<u64, Medal>,
+}
+
+impl Medals {
+    pub fn load(path: &Path) -> Medals {
+        Medals::parse(&paths::read(path))
+    }
+
+    /// Reads a `version 1` line followed by a `hash medal` line per map. Lines that can't
+    /// be read are skipped, so a broken file never stops the game.
+    ///
+    /// Files without a version line are version 1 too. A version this build doesn't know,
+    /// like one written by a newer build, has no medals it can trust.
+    pub fn parse(source: &str) -> Medals {
+        let mut medals = Medals::default();
+        let mut lines = source.lines().peekable();
+        let version = lines
+            .next_if(|line| line.starts_with("version "))
+            .map_or(Some(1), |line| line["version ".len()..].trim().parse().ok());
+        if version != Some(VERSION) {
+            return medals;
+        }
+        for line in lines {
+            let mut fields = line.split(' ');
+            let hash = fields.next().and_then(|f| u64::from_str_radix(f, 16).ok());
+            let medal = fields.next().and_then(Medal::parse);
+            if let (Some(hash), Some(medal)) = (hash, medal) {
+                medals.award(hash, medal);
+            }
+        }
+        medals
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut contents = format!("version {}\n", VERSION);
+        for (hash, medal) in &self.maps {
+            contents.push_str(&format!("{:016x} {}\n", hash, medal.key()));
+        }
+        paths::write(path, &contents)
+    }
+
+    /// Keeps `medal` if it's the first or a better one won on `map`. Returns whether it
+    /// was kept, for the medals to be saved.
+    pub fn award(&mut self, map: u64, medal: Medal) -> bool {
+        match self.maps.get(&map) {
+            Some(best) if *best >= medal => false,
+            _ => {
+                self.maps.insert(map, medal);
+                true
+            }
+        }
+    }
+}
+
+/// The splits of a run against the clock, taken at each checkpoint it crosses.
+#[derive(Default)]
+pub struct TimeTrial {
+    /// Coordinates of the checkpoints crossed so far, in the order they were.
+    reached: Vec<(usize, usize)>,
+    /// Level time when each of `reached` was.
+    splits: Vec<f32>,
+}
+
+impl TimeTrial {
+    /// Takes a split if `(x, y)` is a checkpoint that wasn't crossed yet.
+    pub fn cross(&mut self, map: &Map, x: usize, y: usize, elapsed: f32) {
+        if map.get(x, y) == Some(CHECKPOINT) && !self.reached.contains(&(x, y)) {
+            self.reached.push((x, y));
+            self.splits.push(elapsed);
+        }
+    }
+
+    /// The level time in big digits in a box at the top center of the screen, with the
+    /// latest splits under it.
+    pub fn draw(
+        &self,
+        screen: &mut Screen,
+        font: &Font,
+        elapsed: f32,
+        locale: &Locale,
+        palette: &Palette,
+    ) {
+        let time = format_time(elapsed);
+        let digits = font.render(&time);
+        let width = digits[0].chars().count() as i32 + 4;
+        let height = font.height() as i32 + 2;
+        let left = (SCREEN_WIDTH as i32 - width) / 2;
+        screen.frame(left, 1, width, height, &Border::DOUBLE, palette.frame);
+        font.draw_centered(screen, 2, &time, palette.highlight);
+        let first = self.splits.len().saturating_sub(SHOWN_SPLITS);
+        let top = 1 + height;
+        for (row, (i, split)) in self.splits.iter().enumerate().skip(first).enumerate() {
+            let number = (i + 1).to_string();
+            let time = format_time(*split);
+            let line = locale.format("time_trial.split", &[("number", &number), ("time", &time)]);
+            screen.centered_string(top + row as i32, &line, palette.text);
+        }
+    }
+}
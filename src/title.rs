@@ -0,0 +1,128 @@
+use crate::{figlet::Font, SCREEN_HEIGHT, SCREEN_WIDTH};
+use olc_fps::{Palette, Screen};
+
+/// Seconds the logo takes to drop into place.
+const DROP_DURATION: f32 = 1.0;
+/// Columns per second travelled by the shine sweeping across the logo.
+const SHINE_SPEED: f32 = 60.0;
+/// Extra columns the shine travels past the logo, giving a pause between sweeps.
+const SHINE_PAUSE: usize = 60;
+
+pub struct TitleScreen {
+    logo: Vec<String>,
+    prompt: String,
+    /// The line of the prompt picked when confirming.
+    selected: usize,
+    elapsed: f32,
+    reduced_motion: bool,
+}
+
+impl TitleScreen {
+    /// Stacks each of `lines` rendered with `font`, one under the other, above a
+    /// blinking `prompt`. Each line of the prompt is centered on its own.
+    pub fn new(font: &Font, lines: &[&str], prompt: String) -> Self {
+        let mut logo = Vec::new();
+        for line in lines {
+            logo.extend(font.render(line));
+            logo.push(String::new());
+        }
+        logo.pop();
+        TitleScreen {
+            logo,
+            prompt,
+            selected: 0,
+            elapsed: 0.0,
+            reduced_motion: false,
+        }
+    }
+
+    /// Shows the logo in place without the shine, and keeps the prompt from blinking.
+    pub fn with_reduced_motion(mut self, reduced_motion: bool) -> Self {
+        self.reduced_motion = reduced_motion;
+        self
+    }
+
+    /// Replaces the prompt, like after the key it names got rebound.
+    pub fn with_prompt(mut self, prompt: String) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Marks `line` of the prompt as the one picked when confirming.
+    pub fn select(&mut self, line: usize) {
+        self.selected = line;
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.elapsed += delta_time;
+    }
+
+    /// Which line of the prompt is drawn on row `y`, to tell what was clicked.
+    pub fn prompt_line_at(&self, y: i32) -> Option<usize> {
+        let line = y - self.prompt_top();
+        let lines = self.prompt.lines().count() as i32;
+        Some(line as usize).filter(|_| line >= 0 && line < lines)
+    }
+
+    /// The row the logo lands on.
+    fn logo_top(&self) -> f32 {
+        (SCREEN_HEIGHT as f32 - self.logo.len() as f32) / 3.0
+    }
+
+    fn prompt_top(&self) -> i32 {
+        self.logo_top() as i32 + self.logo.len() as i32 + 4
+    }
+
+    pub fn draw(&self, screen: &mut Screen, palette: &Palette) {
+        screen.clear(' ', palette.background);
+
+        // The logo eases in from above the screen, then a shine keeps sweeping across it
+        let height = self.logo.len() as f32;
+        let target = self.logo_top();
+        let t = match self.reduced_motion {
+            true => 1.0,
+            false => (self.elapsed / DROP_DURATION).min(1.0),
+        };
+        let ease = 1.0 - (1.0 - t) * (1.0 - t);
+        let top = (-height + (target + height) * ease) as i32;
+        let sweep = self
+            .logo
+            .iter()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0)
+            + self.logo.len()
+            + SHINE_PAUSE;
+        let shine = ((self.elapsed - DROP_DURATION).max(0.0) * SHINE_SPEED) as i32 % sweep as i32;
+
+        for (row, line) in self.logo.iter().enumerate() {
+            let y = top + row as i32;
+            let left = (SCREEN_WIDTH as i32 - line.chars().count() as i32) / 2;
+            for (col, c) in line.chars().enumerate() {
+                let diagonal = col as i32 + row as i32;
+                let c = match shine - diagonal {
+                    _ if c == ' ' || t < 1.0 || self.reduced_motion => c,
+                    0..=1 => '\u{2592}',
+                    2..=3 => '\u{2593}',
+                    _ => c,
+                };
+                screen.set_cell(left + col as i32, y, c, palette.highlight);
+            }
+        }
+
+        // Blink the prompt once the logo has landed
+        if t >= 1.0 && (self.reduced_motion || self.elapsed.fract() < 0.6) {
+            for (row, line) in self.prompt.lines().enumerate() {
+                let y = self.prompt_top() + row as i32;
+                match row == self.selected {
+                    true => screen.centered_string(y, &format!("> {} <", line), palette.highlight),
+                    false => screen.centered_string(y, line, palette.text),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use olc_fps::{
+    math::{direction, Vec2},
+    Map,
+};
+
+/// Floor open to the sky, where weather falls.
+pub const OUTDOORS: char = ',';
+
+/// How many particles fall around the player at once.
+const PARTICLES: usize = 300;
+/// Tiles around the player that particles fall within.
+const RADIUS: f32 = 6.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Rain,
+    Snow,
+}
+
+impl Kind {
+    /// Heights per second, a particle falls from a height of 1 at the ceiling to -1.
+    fn fall_speed(self) -> f32 {
+        match self {
+            Kind::Rain => 4.0,
+            Kind::Snow => 0.4,
+        }
+    }
+
+    /// How much the wind carries particles, snow drifts a lot more than rain.
+    fn drift(self) -> f32 {
+        match self {
+            Kind::Rain => 0.3,
+            Kind::Snow => 1.0,
+        }
+    }
+
+    /// Drawn nearer than `distance`.
+    pub fn glyph(self, distance: f32) -> char {
+        match (self, distance < RADIUS / 2.0) {
+            (Kind::Rain, true) => '|',
+            (Kind::Rain, false) => '\'',
+            (Kind::Snow, true) => '*',
+            (Kind::Snow, false) => '.',
+        }
+    }
+}
+
+pub struct Particle {
+    pub position: Vec2,
+    /// From -1 on the floor to 1 at the top of the walls.
+    pub height: f32,
+}
+
+/// Particles falling over the outdoor tiles around the player, carried by a gusting wind.
+pub struct Weather {
+    pub kind: Kind,
+    particles: Vec<Particle>,
+    /// Radians the wind blows towards, with the same convention as the player's angle.
+    wind_angle: f32,
+    /// Tiles per second at the strongest gusts.
+    wind_speed: f32,
+    elapsed: f32,
+    seed: u32,
+}
+
+impl Weather {
+    pub fn new(kind: Kind) -> Self {
+        Weather {
+            kind,
+            particles: Vec::new(),
+            wind_angle: 0.8,
+            wind_speed: 1.5,
+            elapsed: 0.0,
+            seed: 0x9e37_79b9,
+        }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Moves the particles along, and puts new ones over outdoor tiles near `around` in
+    /// place of those that landed or blew too far away. New ones start at any height so
+    /// there are as many at every height.
+    pub fn update(&mut self, map: &Map, around: Vec2, delta_time: f32) {
+        self.elapsed += delta_time;
+        let gust = 0.6 + 0.4 * (self.elapsed * 0.7).sin();
+        let wind = direction(self.wind_angle) * self.wind_speed * gust * self.kind.drift();
+        let fall = self.kind.fall_speed() * delta_time;
+        for particle in &mut self.particles {
+            particle.position += wind * delta_time;
+            particle.height -= fall;
+        }
+        self.particles.retain(|p| {
+            p.height > -1.0
+                && p.position.distance(around) < RADIUS
+                && map.at(p.position.x, p.position.y) == Some(OUTDOORS)
+        });
+        // Spots that aren't outdoors are skipped instead of tried again, so being indoors
+        // costs little
+        for _ in 0..PARTICLES - self.particles.len() {
+            let offset = Vec2::new(self.random() * 2.0 - 1.0, self.random() * 2.0 - 1.0);
+            let position = around + offset * RADIUS;
+            if map.at(position.x, position.y) == Some(OUTDOORS) {
+                let height = 1.0 - self.random() * 2.0;
+                self.particles.push(Particle { position, height });
+            }
+        }
+    }
+
+    /// From 0 to 1, xorshift is plenty for where drops fall.
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32
+    }
+}
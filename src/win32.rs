@@ -0,0 +1,209 @@
+//! Safe wrappers over the Win32 console calls the engine makes.
+//!
+//! Every result is checked, failures come back as the `io::Error` for `GetLastError`.
+
+use std::{io, mem, ptr};
+use winapi::{
+    shared::{
+        minwindef::{BOOL, DWORD},
+        ntdef::NULL,
+    },
+    um::{
+        consoleapi::{
+            GetConsoleMode, GetNumberOfConsoleInputEvents, ReadConsoleInputW, SetConsoleMode,
+        },
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        processenv::GetStdHandle,
+        winbase::{STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
+        wincon::{
+            CreateConsoleScreenBuffer, SetConsoleActiveScreenBuffer, SetConsoleCursorInfo,
+            SetConsoleTitleW, WriteConsoleOutputAttribute, WriteConsoleOutputCharacterW,
+            CONSOLE_CURSOR_INFO, CONSOLE_TEXTMODE_BUFFER, ENABLE_EXTENDED_FLAGS,
+            ENABLE_MOUSE_INPUT, ENABLE_QUICK_EDIT_MODE,
+        },
+        wincontypes::{
+            COORD, FOCUS_EVENT, FROM_LEFT_1ST_BUTTON_PRESSED, FROM_LEFT_2ND_BUTTON_PRESSED,
+            INPUT_RECORD, MOUSE_EVENT, MOUSE_WHEELED, RIGHTMOST_BUTTON_PRESSED,
+        },
+        winnt::{GENERIC_READ, GENERIC_WRITE, HANDLE},
+        winuser::GetAsyncKeyState,
+    },
+};
+
+/// Turns the `BOOL` most console functions return into a `Result`.
+fn check(result: BOOL) -> io::Result<()> {
+    match result {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+fn check_handle(handle: HANDLE) -> io::Result<HANDLE> {
+    match handle {
+        NULL | INVALID_HANDLE_VALUE => Err(io::Error::last_os_error()),
+        handle => Ok(handle),
+    }
+}
+
+/// A console screen buffer shown in place of the one the game was started from, which
+/// comes back when this is dropped. Its cursor is hidden.
+pub struct ScreenBuffer {
+    handle: HANDLE,
+    previous: HANDLE,
+}
+
+impl ScreenBuffer {
+    pub fn new() -> io::Result<Self> {
+        let previous = check_handle(unsafe { GetStdHandle(STD_OUTPUT_HANDLE) })?;
+        let handle = check_handle(unsafe {
+            CreateConsoleScreenBuffer(
+                GENERIC_READ | GENERIC_WRITE,
+                0,
+                ptr::null(),
+                CONSOLE_TEXTMODE_BUFFER,
+                NULL,
+            )
+        })?;
+        // Built before activating so the handle gets closed if that fails
+        let buffer = ScreenBuffer { handle, previous };
+        let cursor = CONSOLE_CURSOR_INFO {
+            dwSize: 1,
+            bVisible: 0,
+        };
+        check(unsafe { SetConsoleCursorInfo(handle, &cursor) })?;
+        check(unsafe { SetConsoleActiveScreenBuffer(handle) })?;
+        Ok(buffer)
+    }
+
+    /// Writes characters and their attributes from the top left corner, row by row.
+    pub fn write(&self, cells: &[u16], colors: &[u16]) -> io::Result<()> {
+        let mut written = 0;
+        let origin = COORD { X: 0, Y: 0 };
+        check(unsafe {
+            WriteConsoleOutputCharacterW(
+                self.handle,
+                cells.as_ptr(),
+                cells.len() as u32,
+                origin,
+                &mut written,
+            )
+        })?;
+        check(unsafe {
+            WriteConsoleOutputAttribute(
+                self.handle,
+                colors.as_ptr(),
+                colors.len() as u32,
+                origin,
+                &mut written,
+            )
+        })
+    }
+}
+
+impl Drop for ScreenBuffer {
+    fn drop(&mut self) {
+        // Nothing left to report failures to, the console is as good as it gets
+        unsafe {
+            SetConsoleActiveScreenBuffer(self.previous);
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+/// Something that happened to the console, read from its input buffer.
+pub enum InputEvent {
+    /// The console window gained (`true`) or lost (`false`) the keyboard focus.
+    Focus(bool),
+    /// The mouse moved, clicked or scrolled over the cell at `(x, y)`.
+    Mouse {
+        x: i32,
+        y: i32,
+        left: bool,
+        right: bool,
+        middle: bool,
+        /// Notches scrolled, positive away from the user.
+        wheel: i32,
+    },
+}
+
+/// The console's input buffer, read without ever blocking.
+///
+/// Mouse input is on and QuickEdit off while this is around: selecting text with the
+/// mouse would otherwise freeze the game until the selection is dismissed. The previous
+/// mode is restored on drop.
+pub struct InputBuffer {
+    handle: HANDLE,
+    previous_mode: DWORD,
+}
+
+impl InputBuffer {
+    pub fn new() -> io::Result<Self> {
+        let handle = check_handle(unsafe { GetStdHandle(STD_INPUT_HANDLE) })?;
+        let mut previous_mode = 0;
+        check(unsafe { GetConsoleMode(handle, &mut previous_mode) })?;
+        let mode =
+            (previous_mode | ENABLE_EXTENDED_FLAGS | ENABLE_MOUSE_INPUT) & !ENABLE_QUICK_EDIT_MODE;
+        check(unsafe { SetConsoleMode(handle, mode) })?;
+        Ok(InputBuffer {
+            handle,
+            previous_mode,
+        })
+    }
+
+    /// Takes every pending event out of the buffer, skipping the kinds that aren't used.
+    pub fn read(&self) -> io::Result<Vec<InputEvent>> {
+        let mut pending = 0;
+        check(unsafe { GetNumberOfConsoleInputEvents(self.handle, &mut pending) })?;
+        if pending == 0 {
+            return Ok(Vec::new());
+        }
+        let mut records: Vec<INPUT_RECORD> = vec![unsafe { mem::zeroed() }; pending as usize];
+        let mut read = 0;
+        check(unsafe { ReadConsoleInputW(self.handle, records.as_mut_ptr(), pending, &mut read) })?;
+        records.truncate(read as usize);
+        let events = records.iter().filter_map(|record| match record.EventType {
+            FOCUS_EVENT => {
+                let focus = unsafe { record.Event.FocusEvent() };
+                Some(InputEvent::Focus(focus.bSetFocus != 0))
+            }
+            MOUSE_EVENT => {
+                let mouse = unsafe { record.Event.MouseEvent() };
+                let buttons = mouse.dwButtonState;
+                // The high word of the button state is the wheel delta, in 120ths of a notch
+                let wheel = match mouse.dwEventFlags & MOUSE_WHEELED {
+                    0 => 0,
+                    _ => (buttons >> 16) as i16 as i32 / 120,
+                };
+                Some(InputEvent::Mouse {
+                    x: mouse.dwMousePosition.X as i32,
+                    y: mouse.dwMousePosition.Y as i32,
+                    left: buttons & FROM_LEFT_1ST_BUTTON_PRESSED != 0,
+                    right: buttons & RIGHTMOST_BUTTON_PRESSED != 0,
+                    middle: buttons & FROM_LEFT_2ND_BUTTON_PRESSED != 0,
+                    wheel,
+                })
+            }
+            _ => None,
+        });
+        Ok(events.collect())
+    }
+}
+
+impl Drop for InputBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            SetConsoleMode(self.handle, self.previous_mode);
+        }
+    }
+}
+
+/// Shows `title` in the console window's title bar.
+pub fn set_title(title: &str) -> io::Result<()> {
+    let wide: Vec<u16> = title.encode_utf16().chain(Some(0)).collect();
+    check(unsafe { SetConsoleTitleW(wide.as_ptr()) })
+}
+
+/// Whether the virtual key is held down right now, whichever window has focus.
+pub fn key_down(key: i32) -> bool {
+    unsafe { GetAsyncKeyState(key) as u16 & 0x8000 != 0 }
+}